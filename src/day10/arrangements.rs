@@ -0,0 +1,134 @@
+use std::fmt;
+use num_traits::Zero;
+use super::{BigUint, ConnectionCombinationCount, JoltageRules};
+
+pub struct Arrangement(pub Vec<usize>);
+
+impl fmt::Display for Arrangement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let jolts: Vec<String> = self.0.iter().map(|jolt| jolt.to_string()).collect();
+        write!(f, "{}", jolts.join(" -> "))
+    }
+}
+
+// depth first walk over the chain, each frame holds the index of a jolt value and the next candidate to try after it
+pub struct Arrangements<'a> {
+    jolts: Vec<usize>,
+    rules: &'a JoltageRules,
+    stack: Vec<(usize, usize)>
+}
+
+impl<'a> Arrangements<'a> {
+    pub fn new(jolts: Vec<usize>, rules: &'a JoltageRules) -> Self {
+        Arrangements { jolts, rules, stack: vec![(0, 1)] }
+    }
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Arrangement;
+
+    fn next(&mut self) -> Option<Arrangement> {
+        while let Some(&(idx, candidate)) = self.stack.last() {
+            if idx + 1 == self.jolts.len() {
+                let arrangement = self.stack.iter().map(|&(idx, _)| self.jolts[idx]).collect();
+                self.stack.pop();
+                return Some(Arrangement(arrangement));
+            }
+            let current = self.jolts[idx];
+            let next = (candidate..self.jolts.len())
+                .take_while(|&next_idx| self.jolts[next_idx] <= current + self.rules.max_difference())
+                .find(|&next_idx| self.rules.accepts(current, self.jolts[next_idx]));
+            match next {
+                Some(next_idx) => {
+                    self.stack.last_mut().unwrap().1 = next_idx + 1;
+                    self.stack.push((next_idx, next_idx + 1));
+                },
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+// SplitMix64, good enough to pick arrangements without pulling in a rand dependency
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // rejection sampling on the bit length of the bound, so every value below it is equally likely
+    fn below(&mut self, bound: &BigUint) -> BigUint {
+        let bits = bound.bits();
+        let words = bits.div_ceil(64);
+        loop {
+            let bytes: Vec<u8> = (0..words).flat_map(|_| self.next_u64().to_le_bytes()).collect();
+            let value = BigUint::from_bytes_le(&bytes) >> (words * 64 - bits);
+            if &value < bound {
+                return value;
+            }
+        }
+    }
+}
+
+// walks from the outlet picking each next adapter with probability proportional to the arrangements it leads to
+pub fn sample(jolts: &[usize], rules: &JoltageRules, random: &mut Random) -> Option<Arrangement> {
+    let arrangements = ConnectionCombinationCount::default().arrangement_counts(jolts, rules);
+    if arrangements.first().is_none_or(Zero::is_zero) {
+        return None;
+    }
+    let mut idx = 0;
+    let mut arrangement = vec![jolts[idx]];
+    while idx + 1 < jolts.len() {
+        let mut choice = random.below(&arrangements[idx]);
+        idx = (idx + 1..jolts.len())
+            .filter(|&next_idx| rules.accepts(jolts[idx], jolts[next_idx]))
+            .find(|&next_idx| {
+                if choice < arrangements[next_idx] {
+                    true
+                } else {
+                    choice -= &arrangements[next_idx];
+                    false
+                }
+            })?;
+        arrangement.push(jolts[idx]);
+    }
+    Some(Arrangement(arrangement))
+}
+
+// an adapter is mandatory when the arrangements through it are all of the arrangements
+pub fn mandatory_adapters(jolts: &[usize], rules: &JoltageRules) -> Vec<usize> {
+    let to_device = ConnectionCombinationCount::default().arrangement_counts(jolts, rules);
+    let total = match to_device.first() {
+        Some(total) if !total.is_zero() => total,
+        _ => return Vec::new()
+    };
+    let mut from_outlet = vec![BigUint::zero(); jolts.len()];
+    from_outlet[0] = BigUint::from(1u32);
+    for idx in 1..jolts.len() {
+        let mut count = BigUint::zero();
+        for previous_idx in (0..idx).rev() {
+            if jolts[previous_idx] + rules.max_difference() < jolts[idx] {
+                break;
+            } else if rules.accepts(jolts[previous_idx], jolts[idx]) {
+                count += &from_outlet[previous_idx];
+            }
+        }
+        from_outlet[idx] = count;
+    }
+    (1..jolts.len() - 1)
+        .filter(|&idx| &(&from_outlet[idx] * &to_device[idx]) == total)
+        .map(|idx| jolts[idx])
+        .collect()
+}
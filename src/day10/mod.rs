@@ -1,84 +1,165 @@
+extern crate num_bigint_dig as num_bigint;
+
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+mod arrangements;
+
+pub use arrangements::{Arrangement, Arrangements, Random};
 
 pub struct GreedyConnectionDiffMultiplication;
-pub struct ConnectionCombinationCount;
+#[derive(Default)]
+pub struct ConnectionCombinationCount {
+    pub modulo: Option<BigUint>
+}
+
+pub struct JoltageRules {
+    pub allowed_differences: BTreeSet<usize>,
+    pub outlet_jolts: usize,
+    pub device_offset: usize
+}
+
+impl Default for JoltageRules {
+    fn default() -> Self {
+        JoltageRules {
+            allowed_differences: (1..=3).collect(),
+            outlet_jolts: 0,
+            device_offset: 3
+        }
+    }
+}
+
+impl JoltageRules {
+    fn accepts(&self, from: usize, to: usize) -> bool {
+        to >= from && self.allowed_differences.contains(&(to - from))
+    }
+
+    fn max_difference(&self) -> usize {
+        self.allowed_differences.iter().next_back().copied().unwrap_or(0)
+    }
+
+    // outlet, adapters and device in ascending order
+    fn chain_jolts(&self, sorted_jolts: &[usize]) -> Vec<usize> {
+        let device_jolts = sorted_jolts.last().copied().unwrap_or(self.outlet_jolts) + self.device_offset;
+        let mut jolts = Vec::with_capacity(sorted_jolts.len() + 2);
+        jolts.push(self.outlet_jolts);
+        jolts.extend_from_slice(sorted_jolts);
+        jolts.push(device_jolts);
+        jolts
+    }
+}
+
+pub struct AdapterChain {
+    pub jolts: Vec<usize>,
+    pub differences: BTreeMap<usize, usize>
+}
+
+impl fmt::Display for AdapterChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let jolts: Vec<String> = self.jolts.iter().map(|jolt| jolt.to_string()).collect();
+        write!(f, "{}", jolts.join(" -> "))?;
+        for (difference, count) in &self.differences {
+            write!(f, "\n{} jolt differences: {}", difference, count)?;
+        }
+        Ok(())
+    }
+}
 
 pub trait Count {
-    fn count(&self, sorted_jolts: &[usize]) -> Option<usize>;
+    fn count(&self, sorted_jolts: &[usize], rules: &JoltageRules) -> Option<BigUint>;
 }
 
 impl GreedyConnectionDiffMultiplication {
-    fn connect_adapters_greedily(&self, sorted_jolts: &[usize]) -> Option<[usize; 3]> {
+    fn connect_adapters_greedily(&self, sorted_jolts: &[usize], rules: &JoltageRules) -> Option<AdapterChain> {
         if sorted_jolts.is_empty() {
             return None;
         }
-        let mut differences = [0, 0, 0];
-    
-        let mut add_difference = |diff| {
-            let index = match diff { 1 => Some(0), 2 => Some(1), 3 => Some(2), _ => None };
-            index.and_then(|index| {
-                differences[index] += 1;
-                Some(differences[index])
-            })
-        };
-    
-        // difference between outlet (0 jolts) and first adapter
-        add_difference(*sorted_jolts.first().unwrap())?;
-    
-        for jolts in sorted_jolts.windows(2) {
-            let prev = jolts[0];
-            let current = jolts[1];
-            add_difference(current - prev)?;
+        let jolts = rules.chain_jolts(sorted_jolts);
+        let mut differences = BTreeMap::new();
+        for pair in jolts.windows(2) {
+            if !rules.accepts(pair[0], pair[1]) {
+                return None;
+            }
+            *differences.entry(pair[1] - pair[0]).or_insert(0) += 1;
         }
-        // difference between device (highest adapter + 3) and highest adapter
-        add_difference(3)?;
-    
-        Some(differences)
+        Some(AdapterChain { jolts, differences })
     }
 }
 
 impl Count for GreedyConnectionDiffMultiplication {
-    fn count(&self, sorted_jolts: &[usize]) -> Option<usize> {
-        self.connect_adapters_greedily(sorted_jolts).and_then(|[diff_1, _diff_2, diff_3]| Some(diff_1 * diff_3))
+    fn count(&self, sorted_jolts: &[usize], rules: &JoltageRules) -> Option<BigUint> {
+        self.connect_adapters_greedily(sorted_jolts, rules).map(|chain| {
+            let count_of = |difference| chain.differences.get(&difference).copied().unwrap_or(0);
+            BigUint::from(count_of(1)) * count_of(3)
+        })
     }
 }
 
 impl ConnectionCombinationCount {
-    fn combination_count_at(&self, sorted_jolts: &[usize], idx: usize, cache: &mut HashMap<usize, usize>) -> usize {
-        if let Some(value) = cache.get(&idx) {
-            return *value;
-        } else if idx + 1 == sorted_jolts.len() {
-            return 1;
+    // arrangements[i] counts the ways to get from the i-th jolt value to the device, filled in from the device backwards
+    pub(crate) fn arrangement_counts(&self, jolts: &[usize], rules: &JoltageRules) -> Vec<BigUint> {
+        let mut arrangements = vec![BigUint::zero(); jolts.len()];
+        if let Some(device) = arrangements.last_mut() {
+            *device = BigUint::one();
         }
-        let current = sorted_jolts.get(idx).unwrap();
-        let mut combination_count = 0;
-        for (i, value) in sorted_jolts[idx + 1..(idx + 4).min(sorted_jolts.len())].iter().enumerate() {
-            if current + 3 >= *value {
-                combination_count += self.combination_count_at(sorted_jolts, idx + i + 1, cache);
-            } else {
-                break;
+        for idx in (0..jolts.len().saturating_sub(1)).rev() {
+            let current = jolts[idx];
+            let mut combination_count = BigUint::zero();
+            for (next_idx, &value) in jolts.iter().enumerate().skip(idx + 1) {
+                if value > current + rules.max_difference() {
+                    break;
+                } else if rules.accepts(current, value) {
+                    combination_count += &arrangements[next_idx];
+                }
             }
+            if let Some(modulo) = &self.modulo {
+                combination_count %= modulo;
+            }
+            arrangements[idx] = combination_count;
         }
-        cache.insert(idx, combination_count);
-        combination_count
+        arrangements
     }
 }
 
 impl Count for ConnectionCombinationCount {
-    fn count(&self, sorted_jolts: &[usize]) -> Option<usize> {
-        let mut numbers = sorted_jolts.to_owned();
-        numbers.insert(0, 0);
-        Some(self.combination_count_at(&numbers, 0, &mut HashMap::with_capacity(numbers.len())))
+    fn count(&self, sorted_jolts: &[usize], rules: &JoltageRules) -> Option<BigUint> {
+        if self.modulo.as_ref().is_some_and(Zero::is_zero) {
+            return None;
+        }
+        let jolts = rules.chain_jolts(sorted_jolts);
+        self.arrangement_counts(&jolts, rules).into_iter().next()
     }
 }
 
-pub fn solve(input: &File, counter: &impl Count) -> Option<usize> {
+fn sorted_input(input: &File) -> Vec<usize> {
     let mut numbers = parse_input(input);
-    numbers.sort_by(|one, other| one.cmp(&other));
-    counter.count(&numbers)
+    numbers.sort_unstable();
+    numbers
+}
+
+pub fn solve(input: &File, counter: &impl Count, rules: &JoltageRules) -> Option<BigUint> {
+    counter.count(&sorted_input(input), rules)
+}
+
+pub fn adapter_chain(input: &File, rules: &JoltageRules) -> Option<AdapterChain> {
+    GreedyConnectionDiffMultiplication.connect_adapters_greedily(&sorted_input(input), rules)
+}
+
+pub fn arrangements<'a>(input: &File, rules: &'a JoltageRules) -> Arrangements<'a> {
+    Arrangements::new(rules.chain_jolts(&sorted_input(input)), rules)
+}
+
+pub fn sample_arrangement(input: &File, rules: &JoltageRules, random: &mut Random) -> Option<Arrangement> {
+    arrangements::sample(&rules.chain_jolts(&sorted_input(input)), rules, random)
+}
+
+pub fn mandatory_adapters(input: &File, rules: &JoltageRules) -> Vec<usize> {
+    arrangements::mandatory_adapters(&rules.chain_jolts(&sorted_input(input)), rules)
 }
 
 fn parse_input(input: &File) -> Vec<usize> {
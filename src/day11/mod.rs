@@ -1,10 +1,52 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::ops::RangeInclusive;
 
 pub struct CloseNeighbourConfiguration;
 pub struct VisibleNeighbourConfiguration;
 
+const MOORE_OFFSETS: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+const VON_NEUMANN_OFFSETS: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+#[derive(Clone)]
+pub enum Neighbourhood {
+    Moore,
+    VonNeumann,
+    LineOfSight { limit: Option<usize> },
+    Custom(Vec<(isize, isize)>)
+}
+
+impl Neighbourhood {
+    // directions to look in and how many tiles to look along each of them
+    fn directions(&self) -> (&[(isize, isize)], Option<usize>) {
+        match self {
+            Neighbourhood::Moore => (&MOORE_OFFSETS, Some(1)),
+            Neighbourhood::VonNeumann => (&VON_NEUMANN_OFFSETS, Some(1)),
+            Neighbourhood::LineOfSight { limit } => (&MOORE_OFFSETS, *limit),
+            Neighbourhood::Custom(offsets) => (offsets, Some(1))
+        }
+    }
+
+    fn parse(spec: &str) -> Option<Self> {
+        let (name, argument) = match spec.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (spec, None)
+        };
+        match (name, argument) {
+            ("moore", None) => Some(Neighbourhood::Moore),
+            ("von-neumann", None) => Some(Neighbourhood::VonNeumann),
+            ("sight", None) => Some(Neighbourhood::LineOfSight { limit: None }),
+            ("sight", Some(limit)) => limit.parse().ok().map(|limit| Neighbourhood::LineOfSight { limit: Some(limit) }),
+            ("offsets", Some(offsets)) => offsets.split(';').map(|offset| {
+                let (row, col) = offset.split_once(',')?;
+                Some((row.trim().parse().ok()?, col.trim().parse().ok()?))
+            }).collect::<Option<Vec<_>>>().map(Neighbourhood::Custom),
+            _ => None
+        }
+    }
+}
+
 pub trait SimulationConfiguration {
     fn get_visibility_limit(&self) -> Option<usize> {
         None
@@ -13,6 +55,20 @@ pub trait SimulationConfiguration {
     fn get_acceptable_neighbours_count(&self) -> usize {
         0
     }
+
+    fn get_neighbourhood(&self) -> Neighbourhood {
+        Neighbourhood::LineOfSight { limit: self.get_visibility_limit() }
+    }
+
+    // occupied neighbour counts at which an empty seat gets taken
+    fn get_birth_range(&self) -> RangeInclusive<usize> {
+        0..=0
+    }
+
+    // occupied neighbour counts at which a taken seat stays taken
+    fn get_survival_range(&self) -> RangeInclusive<usize> {
+        0..=self.get_acceptable_neighbours_count()
+    }
 }
 
 impl SimulationConfiguration for CloseNeighbourConfiguration {
@@ -31,81 +87,171 @@ impl SimulationConfiguration for VisibleNeighbourConfiguration {
     }
 }
 
+// rules written as "B<birth>/S<survival>/<neighbourhood>", e.g. "B0/S0-3/moore" or "B0/S0-4/sight"
+pub struct RuleConfiguration {
+    pub neighbourhood: Neighbourhood,
+    pub birth: RangeInclusive<usize>,
+    pub survival: RangeInclusive<usize>
+}
+
+impl RuleConfiguration {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let parse_range = |range: &str| -> Option<RangeInclusive<usize>> {
+            match range.split_once('-') {
+                Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+                None => range.parse().ok().map(|count| count..=count)
+            }
+        };
+        let mut parts = spec.splitn(3, '/');
+        let birth = parse_range(parts.next()?.strip_prefix('B')?)?;
+        let survival = parse_range(parts.next()?.strip_prefix('S')?)?;
+        let neighbourhood = Neighbourhood::parse(parts.next()?)?;
+        Some(RuleConfiguration { neighbourhood, birth, survival })
+    }
+}
+
+impl SimulationConfiguration for RuleConfiguration {
+    fn get_neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood.clone()
+    }
+
+    fn get_birth_range(&self) -> RangeInclusive<usize> {
+        self.birth.clone()
+    }
+
+    fn get_survival_range(&self) -> RangeInclusive<usize> {
+        self.survival.clone()
+    }
+}
+
 const EMPTY_SEAT: char = 'L';
 const OCCUPIED_SEAT: char = '#';
 const FLOOR: char = '.';
+const MAX_GENERATIONS: usize = 100_000;
 
-fn get_neighbours(map: &Vec<Vec<char>>, location: (usize, usize), step_limit: Option<usize>) -> Vec<char> {
-    let mut neighbours = Vec::with_capacity(8);
-    let bounds =  (0, map.len() as isize, 0, map.get(location.0).unwrap().len() as isize);
-    let step_limit = step_limit.unwrap_or(map.len());
-    for (row_offset, col_offset) in &[(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)] {
-        let mut neighbour_row = location.0 as isize;
-        let mut neighbour_col = location.1 as isize;
-        let mut step = 0;
-        loop {
-            neighbour_row += row_offset;
-            neighbour_col += col_offset;
-            if step >= step_limit || neighbour_row < bounds.0 || neighbour_row >= bounds.1
-                || neighbour_col < bounds.2 || neighbour_col >= bounds.3 {
-                    break
-            } else if let Some(tile) = map.get(neighbour_row as usize).and_then(|row| row.get(neighbour_col as usize)) {
-                if tile != &FLOOR {
-                    neighbours.push(tile.to_owned());
-                    break;
+// the hall as a flat row-major grid, with each seat's visible seats precomputed in CSR form
+struct SeatingHall {
+    tiles: Vec<char>,
+    neighbour_offsets: Vec<usize>,
+    neighbours: Vec<usize>,
+    // the reverse lists, which differ from the neighbours for asymmetric custom offsets
+    dependent_offsets: Vec<usize>,
+    dependents: Vec<usize>
+}
+
+impl SeatingHall {
+    fn new(map: Vec<Vec<char>>, neighbourhood: &Neighbourhood) -> Self {
+        let height = map.len();
+        let width = map.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut tiles = Vec::with_capacity(height * width);
+        for mut row in map {
+            row.resize(width, FLOOR);
+            tiles.extend(row);
+        }
+        let (directions, step_limit) = neighbourhood.directions();
+        let step_limit = step_limit.unwrap_or_else(|| height.max(width));
+        let mut neighbour_offsets = Vec::with_capacity(tiles.len() + 1);
+        let mut neighbours = Vec::new();
+        neighbour_offsets.push(0);
+        for (cell, &tile) in tiles.iter().enumerate() {
+            if tile != FLOOR {
+                for &(row_offset, col_offset) in directions {
+                    let (mut row, mut col) = ((cell / width) as isize, (cell % width) as isize);
+                    for _ in 0..step_limit {
+                        row += row_offset;
+                        col += col_offset;
+                        if row < 0 || row >= height as isize || col < 0 || col >= width as isize {
+                            break;
+                        }
+                        let neighbour = row as usize * width + col as usize;
+                        if tiles[neighbour] != FLOOR {
+                            neighbours.push(neighbour);
+                            break;
+                        }
+                    }
                 }
             }
-            step += 1;
+            neighbour_offsets.push(neighbours.len());
         }
+        let mut dependent_offsets = vec![0; tiles.len() + 1];
+        for &neighbour in &neighbours {
+            dependent_offsets[neighbour + 1] += 1;
+        }
+        for cell in 0..tiles.len() {
+            dependent_offsets[cell + 1] += dependent_offsets[cell];
+        }
+        let mut dependents = vec![0; neighbours.len()];
+        let mut next_slot = dependent_offsets.clone();
+        for cell in 0..tiles.len() {
+            for &neighbour in &neighbours[neighbour_offsets[cell]..neighbour_offsets[cell + 1]] {
+                dependents[next_slot[neighbour]] = cell;
+                next_slot[neighbour] += 1;
+            }
+        }
+        SeatingHall { tiles, neighbour_offsets, neighbours, dependent_offsets, dependents }
     }
-    neighbours
-}
 
-fn count_occupation(tiles: &[char]) -> (usize, usize) {
-    let mut occupation = (0, 0);
-    for tile in tiles {
-        match tile {
-            &EMPTY_SEAT => occupation.0 += 1,
-            &OCCUPIED_SEAT => occupation.1 += 1,
-            _ => continue
-        }
+    fn neighbours(&self, cell: usize) -> &[usize] {
+        &self.neighbours[self.neighbour_offsets[cell]..self.neighbour_offsets[cell + 1]]
+    }
+
+    fn dependents(&self, cell: usize) -> &[usize] {
+        &self.dependents[self.dependent_offsets[cell]..self.dependent_offsets[cell + 1]]
     }
-    occupation
-}
 
-fn step_simulation(map: &Vec<Vec<char>>, config: &impl SimulationConfiguration) -> Vec<Vec<char>> {
-    let mut new_map = Vec::with_capacity(map.len());
-    for (row_idx, row) in map.iter().enumerate() {
-        let mut new_row = Vec::with_capacity(row.len());
-        for (col_idx, seat) in row.iter().enumerate() {
-            let (_empty, taken) = count_occupation(&get_neighbours(map, (row_idx, col_idx), config.get_visibility_limit()));
-            if seat == &EMPTY_SEAT && taken == 0 {
-                new_row.push(OCCUPIED_SEAT);
-            } else if seat == &OCCUPIED_SEAT && taken > config.get_acceptable_neighbours_count() {
-                new_row.push(EMPTY_SEAT);
-            } else {
-                new_row.push(seat.to_owned());
+    // runs until no seat changes, only revisiting seats that see the ones that changed in the previous generation
+    fn settle(&mut self, config: &impl SimulationConfiguration) -> Option<usize> {
+        let birth_range = config.get_birth_range();
+        let survival_range = config.get_survival_range();
+        let mut next_tiles = self.tiles.clone();
+        let mut changed: Vec<usize> = Vec::new();
+        let mut candidates: Vec<usize> = (0..self.tiles.len()).filter(|&cell| self.tiles[cell] != FLOOR).collect();
+        let mut is_candidate = vec![false; self.tiles.len()];
+        for _ in 0..MAX_GENERATIONS {
+            // the buffers only differ in the seats that changed last time
+            for &cell in &changed {
+                next_tiles[cell] = self.tiles[cell];
+            }
+            changed.clear();
+            for &cell in &candidates {
+                let taken = self.neighbours(cell).iter().filter(|&&neighbour| self.tiles[neighbour] == OCCUPIED_SEAT).count();
+                let seat = self.tiles[cell];
+                let new_seat = if seat == EMPTY_SEAT && birth_range.contains(&taken) {
+                    OCCUPIED_SEAT
+                } else if seat == OCCUPIED_SEAT && !survival_range.contains(&taken) {
+                    EMPTY_SEAT
+                } else {
+                    seat
+                };
+                next_tiles[cell] = new_seat;
+                if new_seat != seat {
+                    changed.push(cell);
+                }
+            }
+            std::mem::swap(&mut self.tiles, &mut next_tiles);
+            if changed.is_empty() {
+                return Some(self.tiles.iter().filter(|&&tile| tile == OCCUPIED_SEAT).count());
+            }
+            candidates.clear();
+            for &cell in &changed {
+                for &affected in self.dependents(cell).iter().chain(std::iter::once(&cell)) {
+                    if !is_candidate[affected] {
+                        is_candidate[affected] = true;
+                        candidates.push(affected);
+                    }
+                }
+            }
+            for &cell in &candidates {
+                is_candidate[cell] = false;
             }
         }
-        new_map.push(new_row);
+        // arbitrary rules may oscillate forever instead of settling
+        None
     }
-    new_map
 }
 
 pub fn solve(input: &File, config: &impl SimulationConfiguration) -> Option<usize> {
-    let mut seating_plan = parse_input(input);
-    loop {
-        let new_plan = step_simulation(&seating_plan, config);
-        let is_same_plan = seating_plan == new_plan;
-        seating_plan = new_plan;
-        if is_same_plan {
-            break
-        }
-    }
-    Some(seating_plan.iter().fold(0, |acc, current| {
-        let (_empty, occupied) = count_occupation(current);
-        acc + occupied
-    }))
+    SeatingHall::new(parse_input(input), &config.get_neighbourhood()).settle(config)
 }
 
 fn parse_input(input: &File) -> Vec<Vec<char>> {
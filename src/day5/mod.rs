@@ -1,43 +1,214 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::fmt;
+use std::convert::TryFrom;
 
 pub enum SearchCriteria {
     HIGHEST,
     EMPTY
 }
 
-struct BoardingPass {
-    row: usize,
-    col: usize
+const OCCUPIED_SEAT: char = '#';
+const FREE_SEAT: char = '.';
+const OWN_SEAT: char = 'O';
+// seat ids must fit into usize and the occupancy analysis keeps a counter per seat
+const MAX_SEAT_BITS: usize = 20;
+
+#[derive(Clone, Copy)]
+pub struct PlaneLayout {
+    row_selector_length: usize,
+    col_selector_length: usize,
+    row_selectors: (char, char),
+    col_selectors: (char, char)
+}
+
+impl Default for PlaneLayout {
+    fn default() -> Self {
+        Self::new(7, 3, ('F', 'B'), ('L', 'R')).unwrap()
+    }
+}
+
+#[derive(Debug)]
+pub enum InvalidPlaneLayout {
+    AmbiguousSelectors { selectors: (char, char) },
+    TooManySeats { bits: usize, limit: usize }
+}
+
+impl fmt::Display for InvalidPlaneLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidPlaneLayout::AmbiguousSelectors { selectors } =>
+                write!(f, "selectors '{}' and '{}' do not tell the halves apart", selectors.0, selectors.1),
+            InvalidPlaneLayout::TooManySeats { bits, limit } =>
+                write!(f, "{} selectors address more seats than the limit of {} bits", bits, limit)
+        }
+    }
+}
+
+impl PlaneLayout {
+    pub fn new(row_selector_length: usize, col_selector_length: usize, row_selectors: (char, char), col_selectors: (char, char)) -> Result<Self, InvalidPlaneLayout> {
+        let bits = row_selector_length.saturating_add(col_selector_length);
+        if bits > MAX_SEAT_BITS {
+            return Err(InvalidPlaneLayout::TooManySeats { bits, limit: MAX_SEAT_BITS });
+        }
+        for &selectors in &[row_selectors, col_selectors] {
+            if selectors.0 == selectors.1 {
+                return Err(InvalidPlaneLayout::AmbiguousSelectors { selectors });
+            }
+        }
+        Ok(PlaneLayout { row_selector_length, col_selector_length, row_selectors, col_selectors })
+    }
+
+    pub fn row_count(&self) -> usize {
+        1 << self.row_selector_length
+    }
+
+    pub fn col_count(&self) -> usize {
+        1 << self.col_selector_length
+    }
+
+    pub fn code_length(&self) -> usize {
+        self.row_selector_length + self.col_selector_length
+    }
+
+    pub fn parse(&self, code: &str) -> Result<BoardingPass, InvalidBoardingPass> {
+        let selectors: Vec<char> = code.chars().collect();
+        if selectors.len() != self.code_length() {
+            return Err(InvalidBoardingPass::WrongLength { expected: self.code_length(), actual: selectors.len() });
+        }
+        let (row_selectors, col_selectors) = selectors.split_at(self.row_selector_length);
+        let row = decode_target(row_selectors, self.row_selectors, 0)?;
+        let col = decode_target(col_selectors, self.col_selectors, self.row_selector_length)?;
+        Ok(BoardingPass { row, col, layout: *self })
+    }
+
+    pub fn pass_for_seat_id(&self, seat_id: usize) -> BoardingPass {
+        BoardingPass {
+            row: seat_id / self.col_count(),
+            col: seat_id % self.col_count(),
+            layout: *self
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InvalidBoardingPass {
+    WrongLength { expected: usize, actual: usize },
+    UnknownSelector { selector: char, position: usize },
+    TooManySelectors { limit: usize, actual: usize }
+}
+
+impl fmt::Display for InvalidBoardingPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidBoardingPass::WrongLength { expected, actual } =>
+                write!(f, "expected {} selectors, but got {}", expected, actual),
+            InvalidBoardingPass::UnknownSelector { selector, position } =>
+                write!(f, "unknown selector '{}' at position {}", selector, position),
+            InvalidBoardingPass::TooManySelectors { limit, actual } =>
+                write!(f, "{} selectors do not fit into {} bits", actual, limit)
+        }
+    }
+}
+
+pub struct ParseError {
+    line_number: usize,
+    error: InvalidBoardingPass
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line_number, self.error)
+    }
+}
+
+pub struct OccupancyReport {
+    pub free_seat_ids: Vec<usize>,
+    pub missing_front_rows: Vec<usize>,
+    pub missing_back_rows: Vec<usize>,
+    pub duplicate_seat_ids: Vec<usize>,
+    pub invalid_passes: Vec<ParseError>
+}
+
+impl fmt::Display for OccupancyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |values: &[usize]| values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(", ");
+        writeln!(f)?;
+        writeln!(f, "Free seats: {}", join(&self.free_seat_ids))?;
+        writeln!(f, "Missing front rows: {}", join(&self.missing_front_rows))?;
+        writeln!(f, "Missing back rows: {}", join(&self.missing_back_rows))?;
+        write!(f, "Duplicate seats: {}", join(&self.duplicate_seat_ids))?;
+        for err in &self.invalid_passes {
+            write!(f, "\nInvalid boarding pass on {}", err)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct BoardingPass {
+    pub row: usize,
+    pub col: usize,
+    layout: PlaneLayout
 }
 
 impl BoardingPass {
-    fn seat_id(&self) -> usize {
-        self.row * 8 + self.col
+    pub fn seat_id(&self) -> usize {
+        self.row * self.layout.col_count() + self.col
+    }
+
+    pub fn code(&self) -> String {
+        encode_target(self.row, self.layout.row_selector_length, self.layout.row_selectors)
+            + &encode_target(self.col, self.layout.col_selector_length, self.layout.col_selectors)
     }
 }
 
-fn search_target(selectors: &str) -> usize {
-    let mut lower_index = 0.0;
-    let mut higher_index = usize::pow(2, selectors.len() as u32) as f64;
-    for row_selector in selectors.chars() {
-        if row_selector == 'F' || row_selector == 'L' {
-            higher_index = lower_index + f64::floor((higher_index - lower_index) / 2.0)
+impl fmt::Display for BoardingPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+fn decode_target(selectors: &[char], halves: (char, char), offset: usize) -> Result<usize, InvalidBoardingPass> {
+    let target = decode_selectors(selectors, halves).map_err(|err| match err {
+        InvalidBoardingPass::UnknownSelector { selector, position } =>
+            InvalidBoardingPass::UnknownSelector { selector, position: offset + position },
+        err => err
+    })?;
+    usize::try_from(target).map_err(|_| InvalidBoardingPass::TooManySelectors { limit: usize::BITS as usize, actual: selectors.len() })
+}
+
+pub fn decode_selectors(selectors: &[char], (lower_half, higher_half): (char, char)) -> Result<u128, InvalidBoardingPass> {
+    if selectors.len() > u128::BITS as usize {
+        return Err(InvalidBoardingPass::TooManySelectors { limit: u128::BITS as usize, actual: selectors.len() });
+    }
+    let mut target = 0;
+    for (position, &selector) in selectors.iter().enumerate() {
+        let bit = if selector == lower_half {
+            0
+        } else if selector == higher_half {
+            1
         } else {
-            lower_index = lower_index + f64::ceil((higher_index - lower_index) / 2.0)
-        }
+            return Err(InvalidBoardingPass::UnknownSelector { selector, position });
+        };
+        target = target << 1 | bit;
     }
-    lower_index as usize
+    Ok(target)
+}
+
+fn encode_target(target: usize, length: usize, (lower_half, higher_half): (char, char)) -> String {
+    (0..length).rev()
+        .map(|bit| if target >> bit & 1 == 0 { lower_half } else { higher_half })
+        .collect()
 }
 
-fn find_highest_seat_id(sorted_boarding_passes: &Vec<BoardingPass>) -> Option<usize> {
-    sorted_boarding_passes.iter().next().and_then(|pass| Some(pass.seat_id()))
+fn find_highest_seat_id(sorted_boarding_passes: &[BoardingPass]) -> Option<usize> {
+    sorted_boarding_passes.iter().next().map(|pass| pass.seat_id())
 }
 
-fn find_free_seat_id(sorted_boarding_passes: &Vec<BoardingPass>) -> Option<usize> {
+fn find_free_seat_id(sorted_boarding_passes: &[BoardingPass]) -> Option<usize> {
     let mut boarding_passes = sorted_boarding_passes.iter();
-    let mut previous_pass_seat_id = boarding_passes.next().unwrap().seat_id();
+    let mut previous_pass_seat_id = boarding_passes.next()?.seat_id();
     for pass in boarding_passes {
         let current_pass_seat_id = pass.seat_id();
         if current_pass_seat_id + 1 != previous_pass_seat_id {
@@ -48,23 +219,118 @@ fn find_free_seat_id(sorted_boarding_passes: &Vec<BoardingPass>) -> Option<usize
     None
 }
 
-pub fn solve(input: &File, search: SearchCriteria) -> Option<usize> {
-    let mut boarding_passes = parse_input(input);
+fn sorted_boarding_passes(input: &File, layout: &PlaneLayout) -> Result<Vec<BoardingPass>, Vec<ParseError>> {
+    let mut boarding_passes = parse_input(input, layout)?;
+    boarding_passes.sort_by_key(|pass| std::cmp::Reverse(pass.seat_id()));
+    Ok(boarding_passes)
+}
+
+pub fn solve(input: &File, layout: &PlaneLayout, search: SearchCriteria) -> Result<Option<usize>, Vec<ParseError>> {
+    let boarding_passes = sorted_boarding_passes(input, layout)?;
     if boarding_passes.is_empty() {
-        return None;
+        return Ok(None);
     }
-    boarding_passes.sort_by(|one, other| other.seat_id().cmp(&one.seat_id()));
-    match search {
+    Ok(match search {
         SearchCriteria::HIGHEST => find_highest_seat_id(&boarding_passes),
         SearchCriteria::EMPTY => find_free_seat_id(&boarding_passes)
+    })
+}
+
+pub fn render_seat_map(input: &File, layout: &PlaneLayout) -> Result<Option<String>, Vec<ParseError>> {
+    let boarding_passes = sorted_boarding_passes(input, layout)?;
+    if boarding_passes.is_empty() {
+        return Ok(None);
+    }
+    let mut seats = vec![vec![FREE_SEAT; layout.col_count()]; layout.row_count()];
+    for pass in &boarding_passes {
+        seats[pass.row][pass.col] = OCCUPIED_SEAT;
+    }
+    if let Some(own_pass) = find_free_seat_id(&boarding_passes).map(|seat_id| layout.pass_for_seat_id(seat_id)) {
+        seats[own_pass.row][own_pass.col] = OWN_SEAT;
+    }
+    let rows: Vec<String> = seats.iter().enumerate()
+        .map(|(row, seats)| format!("{:>3} {}", row, seats.iter().collect::<String>()))
+        .collect();
+    Ok(Some(format!("\n{}", rows.join("\n"))))
+}
+
+pub fn analyse_occupancy(input: &File, layout: &PlaneLayout) -> OccupancyReport {
+    let mut pass_counts = vec![0; layout.row_count() * layout.col_count()];
+    let mut invalid_passes = Vec::new();
+    for (line_idx, line) in BufReader::new(input).lines().enumerate() {
+        match layout.parse(&line.unwrap()) {
+            Ok(pass) => pass_counts[pass.seat_id()] += 1,
+            Err(error) => invalid_passes.push(ParseError { line_number: line_idx + 1, error })
+        }
+    }
+    let duplicate_seat_ids = (0..pass_counts.len()).filter(|&seat_id| pass_counts[seat_id] > 1).collect();
+
+    let first_seat_id = pass_counts.iter().position(|&count| count > 0);
+    let last_seat_id = pass_counts.iter().rposition(|&count| count > 0);
+    let (free_seat_ids, missing_front_rows, missing_back_rows) = match (first_seat_id, last_seat_id) {
+        (Some(first_seat_id), Some(last_seat_id)) => (
+            (first_seat_id..last_seat_id).filter(|&seat_id| pass_counts[seat_id] == 0).collect(),
+            (0..first_seat_id / layout.col_count()).collect(),
+            (last_seat_id / layout.col_count() + 1..layout.row_count()).collect()
+        ),
+        _ => (Vec::new(), (0..layout.row_count()).collect(), Vec::new())
+    };
+    OccupancyReport { free_seat_ids, missing_front_rows, missing_back_rows, duplicate_seat_ids, invalid_passes }
+}
+
+fn parse_input(input: &File, layout: &PlaneLayout) -> Result<Vec<BoardingPass>, Vec<ParseError>> {
+    let mut boarding_passes = Vec::new();
+    let mut errors = Vec::new();
+    for (line_idx, line) in BufReader::new(input).lines().enumerate() {
+        match layout.parse(&line.unwrap()) {
+            Ok(pass) => boarding_passes.push(pass),
+            Err(error) => errors.push(ParseError { line_number: line_idx + 1, error })
+        }
+    }
+    if errors.is_empty() {
+        Ok(boarding_passes)
+    } else {
+        Err(errors)
     }
 }
 
-fn parse_input(input: &File) -> Vec<BoardingPass> {
-    BufReader::new(input).lines().map(|line| {
-        let line = line.unwrap();
-        let row = search_target(&line[0..7]);
-        let col = search_target(&line[7..]);
-        BoardingPass { row, col }
-    }).collect()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn float_search_target(selectors: &str) -> usize {
+        let mut lower_index = 0.0;
+        let mut higher_index = usize::pow(2, selectors.len() as u32) as f64;
+        for row_selector in selectors.chars() {
+            if row_selector == 'F' || row_selector == 'L' {
+                higher_index = lower_index + f64::floor((higher_index - lower_index) / 2.0)
+            } else {
+                lower_index = lower_index + f64::ceil((higher_index - lower_index) / 2.0)
+            }
+        }
+        lower_index as usize
+    }
+
+    #[test]
+    fn decode_selectors_matches_float_search() {
+        for halves in &[('F', 'B'), ('L', 'R')] {
+            for length in 0..=12 {
+                for target in 0..1 << length {
+                    let code = encode_target(target, length, *halves);
+                    let selectors: Vec<char> = code.chars().collect();
+                    assert_eq!(decode_selectors(&selectors, *halves).unwrap(), float_search_target(&code) as u128, "{}", code);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn layout_rejects_oversized_and_ambiguous_selectors() {
+        assert!(matches!(PlaneLayout::new(64, 3, ('F', 'B'), ('L', 'R')), Err(InvalidPlaneLayout::TooManySeats { bits: 67, .. })));
+        assert!(matches!(PlaneLayout::new(usize::MAX, 1, ('F', 'B'), ('L', 'R')), Err(InvalidPlaneLayout::TooManySeats { .. })));
+        assert!(matches!(PlaneLayout::new(7, 3, ('F', 'F'), ('L', 'R')), Err(InvalidPlaneLayout::AmbiguousSelectors { .. })));
+        let layout = PlaneLayout::new(17, 3, ('F', 'B'), ('L', 'R')).unwrap();
+        let pass = layout.parse(&format!("{}RRR", "B".repeat(17))).unwrap();
+        assert_eq!(pass.seat_id(), (1 << 20) - 1);
+    }
 }
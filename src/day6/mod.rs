@@ -1,45 +1,247 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
-use std::collections::HashSet;
-use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const QUESTION_COUNT: usize = 26;
 
 pub struct AnyonesAnswer;
 pub struct EveryonesAnswer;
+pub struct AtLeastAnswer(pub usize);
+pub struct ExactlyOneAnswer;
+pub struct SymmetricDifferenceAnswer;
 
 pub trait GroupAnswerParser {
-    fn parse(&self, group: &str) -> String;
+    fn accepts(&self, answer_count: usize, member_count: usize) -> bool;
+
+    fn parse(&self, group: &Group) -> AnswerSet {
+        let mut answers = AnswerSet::default();
+        for (question, &answer_count) in group.answer_counts.iter().enumerate() {
+            if answer_count > 0 && self.accepts(answer_count, group.member_count) {
+                answers.0 |= 1 << question;
+            }
+        }
+        answers
+    }
 }
 
 impl GroupAnswerParser for AnyonesAnswer {
-    fn parse(&self, group: &str) -> String {
-        let unique_answers: HashSet<char> = group.chars().filter(|c| !c.is_whitespace()).collect();
-        unique_answers.iter().collect()
+    fn accepts(&self, answer_count: usize, _member_count: usize) -> bool {
+        answer_count > 0
+    }
+
+    fn parse(&self, group: &Group) -> AnswerSet {
+        group.anyones_answers
     }
 }
 
 impl GroupAnswerParser for EveryonesAnswer {
-    fn parse(&self, group: &str) -> String {
-        let mut unique_answers: Vec<HashSet<char>> = group.split_whitespace()
-            .map(|answer| answer.chars().collect())
-            .collect();
-        let base_set = unique_answers.pop().unwrap();
-        base_set.iter().filter(|answer| unique_answers.iter().all(|set| set.contains(answer))).collect()
+    fn accepts(&self, answer_count: usize, member_count: usize) -> bool {
+        answer_count == member_count
+    }
+
+    fn parse(&self, group: &Group) -> AnswerSet {
+        group.everyones_answers
+    }
+}
+
+impl GroupAnswerParser for AtLeastAnswer {
+    fn accepts(&self, answer_count: usize, _member_count: usize) -> bool {
+        answer_count >= self.0
+    }
+}
+
+impl GroupAnswerParser for ExactlyOneAnswer {
+    fn accepts(&self, answer_count: usize, _member_count: usize) -> bool {
+        answer_count == 1
+    }
+}
+
+impl GroupAnswerParser for SymmetricDifferenceAnswer {
+    fn accepts(&self, answer_count: usize, _member_count: usize) -> bool {
+        answer_count % 2 == 1
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnswerSet(u32);
+
+impl AnswerSet {
+    // the first answer that is not a question is returned as the error
+    fn parse(answers: &str) -> Result<Self, char> {
+        let mut set = AnswerSet::default();
+        for answer in answers.chars() {
+            if !answer.is_ascii_lowercase() {
+                return Err(answer);
+            }
+            set.0 |= 1 << (answer as u8 - b'a');
+        }
+        Ok(set)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn contains(&self, question: char) -> bool {
+        question.is_ascii_lowercase() && self.0 & 1 << (question as u8 - b'a') != 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = char> + '_ {
+        (b'a'..=b'z').map(char::from).filter(move |&question| self.contains(question))
+    }
+}
+
+impl fmt::Display for AnswerSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.iter().collect::<String>())
+    }
+}
+
+pub struct Group {
+    pub member_count: usize,
+    pub anyones_answers: AnswerSet,
+    pub everyones_answers: AnswerSet,
+    answer_counts: [usize; QUESTION_COUNT]
+}
+
+impl Group {
+    fn new() -> Self {
+        Self {
+            member_count: 0,
+            anyones_answers: AnswerSet::default(),
+            everyones_answers: AnswerSet(u32::MAX),
+            answer_counts: [0; QUESTION_COUNT]
+        }
+    }
+
+    fn add_member(&mut self, answers: AnswerSet) {
+        self.member_count += 1;
+        self.anyones_answers.0 |= answers.0;
+        self.everyones_answers.0 &= answers.0;
+        for question in 0..QUESTION_COUNT {
+            self.answer_counts[question] += (answers.0 >> question & 1) as usize;
+        }
+    }
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} members, union {} ({}), intersection {} ({})", self.member_count,
+            self.anyones_answers.len(), self.anyones_answers,
+            self.everyones_answers.len(), self.everyones_answers)
+    }
+}
+
+pub struct ParseError {
+    line_number: usize,
+    answer: char
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: unexpected answer '{}'", self.line_number, self.answer)
+    }
+}
+
+pub struct GroupReader<R: BufRead> {
+    reader: R,
+    line: String,
+    line_number: usize
+}
+
+impl<R: BufRead> GroupReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, line: String::new(), line_number: 0 }
+    }
+}
+
+impl<R: BufRead> Iterator for GroupReader<R> {
+    type Item = Result<Group, Vec<ParseError>>;
+
+    // members are separated by whitespace, groups by blank lines
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut group = Group::new();
+        let mut errors = Vec::new();
+        let mut has_members = false;
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line).unwrap() == 0 {
+                break;
+            }
+            self.line_number += 1;
+            let mut members = self.line.split_whitespace().peekable();
+            if members.peek().is_none() {
+                if has_members {
+                    break;
+                }
+                continue;
+            }
+            has_members = true;
+            for member in members {
+                match AnswerSet::parse(member) {
+                    Ok(answers) => group.add_member(answers),
+                    Err(answer) => errors.push(ParseError { line_number: self.line_number, answer })
+                }
+            }
+        }
+        if !has_members {
+            None
+        } else if errors.is_empty() {
+            Some(Ok(group))
+        } else {
+            Some(Err(errors))
+        }
+    }
+}
+
+pub struct QuestionHistogram(pub BTreeMap<char, usize>);
+
+impl fmt::Display for QuestionHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (question, count) in &self.0 {
+            write!(f, "\n{}: {}", question, count)?;
+        }
+        Ok(())
+    }
+}
+
+fn for_each_group(input: &File, mut f: impl FnMut(Group)) -> Result<(), Vec<ParseError>> {
+    let mut errors = Vec::new();
+    for group in parse_input(input) {
+        match group {
+            Ok(group) => f(group),
+            Err(group_errors) => errors.extend(group_errors)
+        }
     }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn solve(input: &File, answer_parser: &impl GroupAnswerParser) -> Result<Option<usize>, Vec<ParseError>> {
+    let mut answer_count = 0;
+    for_each_group(input, |group| answer_count += answer_parser.parse(&group).len())?;
+    Ok(Some(answer_count))
 }
 
-pub fn solve(input: &File, answer_parser: &impl GroupAnswerParser) -> Option<usize> {
-    Some(parse_input(input, answer_parser))
+pub fn question_histogram(input: &File, answer_parser: &impl GroupAnswerParser) -> Result<Option<QuestionHistogram>, Vec<ParseError>> {
+    let mut histogram = BTreeMap::new();
+    for_each_group(input, |group| {
+        for answer in answer_parser.parse(&group).iter() {
+            *histogram.entry(answer).or_insert(0) += 1;
+        }
+    })?;
+    Ok(Some(QuestionHistogram(histogram)))
 }
 
-fn parse_input(input: &File, answer_parser: &impl GroupAnswerParser) -> usize {
-    let mut reader = BufReader::new(input);
-    let mut contents = String::new();
-    reader.read_to_string(&mut contents).unwrap();
+pub fn group_breakdown(input: &File) -> impl Iterator<Item = Result<Group, Vec<ParseError>>> + '_ {
+    parse_input(input)
+}
 
-    let new_entry_pattern = Regex::new(r"(?m)(\r\n|\n){2}").unwrap();
-    new_entry_pattern.split(contents.as_str())
-        .map(|group| answer_parser.parse(group))
-        .map(|group| group.len())
-        .sum()
+fn parse_input(input: &File) -> GroupReader<BufReader<&File>> {
+    GroupReader::new(BufReader::new(input))
 }
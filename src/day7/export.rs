@@ -0,0 +1,68 @@
+use std::collections::BTreeSet;
+use super::BagGraph;
+
+const HIGHLIGHT_COLOR: &str = "#ffd700";
+
+#[derive(Default)]
+pub struct GraphExport<'a> {
+    pub subgraph_root: Option<&'a str>,
+    pub highlighted: BTreeSet<&'a str>
+}
+
+impl BagGraph {
+    pub fn to_dot(&self, export: &GraphExport) -> String {
+        let escape = |name: &str| name.replace('"', "\\\"");
+        let mut lines = vec!["digraph bags {".to_owned()];
+        let bags = self.exported_bags(export);
+        for &bag in &bags {
+            let name = self.name(bag);
+            if export.highlighted.contains(name) {
+                lines.push(format!("    \"{}\" [style=filled, fillcolor=\"{}\"];", escape(name), HIGHLIGHT_COLOR));
+            } else {
+                lines.push(format!("    \"{}\";", escape(name)));
+            }
+        }
+        for &bag in &bags {
+            for (child, cost) in self.children.edges(bag) {
+                lines.push(format!("    \"{}\" -> \"{}\" [label=\"{}\"];", escape(self.name(bag)), escape(self.name(child)), cost));
+            }
+        }
+        lines.push("}".to_owned());
+        lines.join("\n")
+    }
+
+    pub fn to_mermaid(&self, export: &GraphExport) -> String {
+        let escape = |name: &str| name.replace('"', "#quot;");
+        let mut lines = vec!["graph TD".to_owned()];
+        let bags = self.exported_bags(export);
+        for &bag in &bags {
+            lines.push(format!("    bag{}[\"{}\"]", bag, escape(self.name(bag))));
+        }
+        for &bag in &bags {
+            for (child, cost) in self.children.edges(bag) {
+                lines.push(format!("    bag{} -->|{}| bag{}", bag, cost, child));
+            }
+        }
+        for &bag in &bags {
+            if export.highlighted.contains(self.name(bag)) {
+                lines.push(format!("    style bag{} fill:{}", bag, HIGHLIGHT_COLOR));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn exported_bags(&self, export: &GraphExport) -> Vec<usize> {
+        match export.subgraph_root {
+            Some(root) => match (self.id(root), self.descendants(root)) {
+                (Some(root), Some(descendants)) => {
+                    let mut bags: Vec<usize> = descendants.into_iter().map(|bag| self.ids[bag]).collect();
+                    bags.insert(0, root);
+                    bags.sort_unstable_by_key(|&bag| self.name(bag));
+                    bags
+                },
+                _ => Vec::new()
+            },
+            None => self.sorted_ids()
+        }
+    }
+}
@@ -1,124 +1,386 @@
+extern crate num_bigint_dig as num_bigint;
+
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::rc::Rc;
 use regex::Regex;
-use std::ptr;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use crate::graph;
+
+mod export;
+
+pub use export::GraphExport;
 
 pub struct UniqueRootsCounter;
 pub struct BagCapacityCounter;
 
 pub trait Counter {
-    fn count(&self, nodes: &HashMap<String, Node>, root: &str) -> Option<usize>;
-}
+    fn count(&self, graph: &BagGraph, root: &str) -> Option<BigUint>;
 
-impl UniqueRootsCounter {
-    fn count_unique_roots(&self, nodes: &HashMap<String, Node>, current: &Node, visited: &mut HashSet<String>) -> usize {
-        let mut roots_count = 0;
-        for edge in &current.parents {
-            let parent_name = &edge.other_node;
-            if visited.contains(parent_name) {
-                continue;
-            }
-            visited.insert(parent_name.to_owned());
-            roots_count += 1 + self.count_unique_roots(nodes, nodes.get(parent_name).unwrap(), visited);
-        }
-        roots_count
-    }
+    fn counted_bags<'a>(&self, graph: &'a BagGraph, root: &str) -> Option<BTreeSet<&'a str>>;
 }
 
 impl Counter for UniqueRootsCounter {
-    fn count(&self, nodes: &HashMap<String, Node>, root: &str) -> Option<usize> {
-        nodes.get(root).and_then(|target| {
-            let mut visited = HashSet::new();
-            visited.insert(root.to_owned());
-            Some(self.count_unique_roots(&nodes, target, &mut visited))
-        })
+    fn count(&self, graph: &BagGraph, root: &str) -> Option<BigUint> {
+        graph.ancestors(root).map(|ancestors| BigUint::from(ancestors.len()))
     }
-}
 
-impl BagCapacityCounter {
-    fn count_bag_capacity(&self, nodes: &HashMap<String, Node>, current: &Node, target: &Node) -> usize {
-        let mut bag_count = 0;
-        for edge in &current.children {
-            let child_name = &edge.other_node;
-            let child_node = nodes.get(child_name).unwrap();
-            if ptr::eq(child_node, target) {
-                panic!("Loop detected")
-            }
-            let child_bag_count = self.count_bag_capacity(nodes, child_node, target);
-            bag_count += edge.cost + child_bag_count * edge.cost;
-        }
-        bag_count
+    fn counted_bags<'a>(&self, graph: &'a BagGraph, root: &str) -> Option<BTreeSet<&'a str>> {
+        graph.ancestors(root)
     }
 }
 
 impl Counter for BagCapacityCounter {
-    fn count(&self, nodes: &HashMap<String, Node>, root: &str) -> Option<usize> {
-        nodes.get(root).and_then(|target| Some(self.count_bag_capacity(&nodes, target, target)))
+    fn count(&self, graph: &BagGraph, root: &str) -> Option<BigUint> {
+        graph.contained_count(root)
+    }
+
+    fn counted_bags<'a>(&self, graph: &'a BagGraph, root: &str) -> Option<BTreeSet<&'a str>> {
+        graph.descendants(root)
     }
 }
 
 #[derive(Debug)]
-pub struct Node {
-    parents: Vec<Edge>,
-    children: Vec<Edge>
+pub enum RuleError {
+    MalformedRule { line_number: usize, line: String },
+    DuplicateRule { bag: String, line_numbers: Vec<usize> },
+    ConflictingQuantity { bag: String, child: String, quantities: Vec<usize> },
+    DanglingReference { bag: String, parent: String },
+    Cycle { bags: Vec<String> }
 }
 
-impl Node {
-    fn new() -> Node {
-        Node {
-            parents: Vec::new(),
-            children: Vec::new(),
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::MalformedRule { line_number, line } =>
+                write!(f, "malformed rule on line {}: {}", line_number, line),
+            RuleError::DuplicateRule { bag, line_numbers } =>
+                write!(f, "{} bags have rules on lines {:?}", bag, line_numbers),
+            RuleError::ConflictingQuantity { bag, child, quantities } =>
+                write!(f, "{} bags contain {} bags in conflicting quantities {:?}", bag, child, quantities),
+            RuleError::DanglingReference { bag, parent } =>
+                write!(f, "{} bags are contained in {} bags, but have no rule", bag, parent),
+            RuleError::Cycle { bags } =>
+                write!(f, "bags contain each other in a cycle: {}", bags.join(", "))
         }
     }
 }
 
-#[derive(Debug)]
-struct Edge {
-    other_node: String,
-    cost: usize
+// compressed sparse rows: the edges of bag `id` are at offsets[id]..offsets[id + 1]
+struct Adjacency {
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    costs: Vec<usize>
+}
+
+impl Adjacency {
+    fn new(bag_count: usize, edges: &[(usize, usize, usize)]) -> Adjacency {
+        let mut offsets = vec![0; bag_count + 1];
+        for &(from, _, _) in edges {
+            offsets[from + 1] += 1;
+        }
+        for id in 0..bag_count {
+            offsets[id + 1] += offsets[id];
+        }
+        let mut next_slot = offsets.clone();
+        let mut targets = vec![0; edges.len()];
+        let mut costs = vec![0; edges.len()];
+        for &(from, to, cost) in edges {
+            targets[next_slot[from]] = to;
+            costs[next_slot[from]] = cost;
+            next_slot[from] += 1;
+        }
+        Adjacency { offsets, targets, costs }
+    }
+
+    fn targets(&self, id: usize) -> &[usize] {
+        &self.targets[self.offsets[id]..self.offsets[id + 1]]
+    }
+
+    fn edges(&self, id: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let range = self.offsets[id]..self.offsets[id + 1];
+        self.targets[range.clone()].iter().copied().zip(self.costs[range].iter().copied())
+    }
+}
+
+pub struct BagPath<'a> {
+    pub bags: Vec<&'a str>,
+    pub multiplicity: BigUint
 }
 
-impl Edge {
-    fn new(other_node: &str, cost: usize) -> Edge {
-        Edge {
-            other_node: other_node.to_owned(),
-            cost
+impl fmt::Display for BagPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (x{})", self.bags.join(" -> "), self.multiplicity)
+    }
+}
+
+// names are shared between the id lookup and the id to name table, so every name is only stored once
+pub struct BagGraph {
+    names: Vec<Rc<str>>,
+    ids: HashMap<Rc<str>, usize>,
+    children: Adjacency,
+    parents: Adjacency,
+    rule_line_numbers: Vec<Vec<usize>>
+}
+
+impl BagGraph {
+    pub fn parse(input: &File) -> Result<BagGraph, Vec<RuleError>> {
+        let graph = parse_input(input)?;
+        let errors = graph.validate();
+        if errors.is_empty() {
+            Ok(graph)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn id(&self, bag: &str) -> Option<usize> {
+        self.ids.get(bag).copied()
+    }
+
+    fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    fn sorted_ids(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = (0..self.names.len()).collect();
+        ids.sort_unstable_by_key(|&id| self.name(id));
+        ids
+    }
+
+    fn validate(&self) -> Vec<RuleError> {
+        let mut errors = Vec::new();
+        for id in self.sorted_ids() {
+            let name = self.name(id);
+            if self.rule_line_numbers[id].len() > 1 {
+                errors.push(RuleError::DuplicateRule { bag: name.to_owned(), line_numbers: self.rule_line_numbers[id].clone() });
+            }
+            let mut child_quantities: Vec<(usize, Vec<usize>)> = Vec::new();
+            for (child, cost) in self.children.edges(id) {
+                match child_quantities.iter_mut().find(|(other, _)| *other == child) {
+                    Some((_, quantities)) => quantities.push(cost),
+                    None => child_quantities.push((child, vec![cost]))
+                }
+            }
+            for (child, mut quantities) in child_quantities {
+                quantities.sort_unstable();
+                quantities.dedup();
+                if quantities.len() > 1 {
+                    errors.push(RuleError::ConflictingQuantity { bag: name.to_owned(), child: self.name(child).to_owned(), quantities });
+                }
+            }
+            if self.rule_line_numbers[id].is_empty() {
+                let mut parents: Vec<&str> = self.parents.targets(id).iter().map(|&parent| self.name(parent)).collect();
+                parents.sort_unstable();
+                parents.dedup();
+                for parent in parents {
+                    errors.push(RuleError::DanglingReference { bag: name.to_owned(), parent: parent.to_owned() });
+                }
+            }
+        }
+        for bags in self.find_cycles() {
+            errors.push(RuleError::Cycle { bags });
+        }
+        errors
+    }
+
+    fn find_cycles(&self) -> Vec<Vec<String>> {
+        graph::find_cycles(self.names.len(), self.sorted_ids(), |id| self.children.targets(id)).into_iter().map(|component| {
+            let mut component: Vec<String> = component.into_iter().map(|member| self.name(member).to_owned()).collect();
+            component.sort_unstable();
+            component
+        }).collect()
+    }
+
+    pub fn roots(&self) -> BTreeSet<&str> {
+        (0..self.names.len()).filter(|&id| self.parents.targets(id).is_empty()).map(|id| self.name(id)).collect()
+    }
+
+    pub fn leaves(&self) -> BTreeSet<&str> {
+        (0..self.names.len()).filter(|&id| self.children.targets(id).is_empty()).map(|id| self.name(id)).collect()
+    }
+
+    pub fn ancestors(&self, bag: &str) -> Option<BTreeSet<&str>> {
+        self.reachable(bag, &self.parents)
+    }
+
+    pub fn descendants(&self, bag: &str) -> Option<BTreeSet<&str>> {
+        self.reachable(bag, &self.children)
+    }
+
+    fn reachable(&self, bag: &str, adjacency: &Adjacency) -> Option<BTreeSet<&str>> {
+        let start = self.id(bag)?;
+        let mut visited = vec![false; self.names.len()];
+        visited[start] = true;
+        let mut reached = BTreeSet::new();
+        let mut stack = vec![start];
+        while let Some(current) = stack.pop() {
+            for &other in adjacency.targets(current) {
+                if !visited[other] {
+                    visited[other] = true;
+                    reached.insert(self.name(other));
+                    stack.push(other);
+                }
+            }
+        }
+        Some(reached)
+    }
+
+    pub fn contained_count(&self, bag: &str) -> Option<BigUint> {
+        self.contained_breakdown(bag).map(|breakdown| breakdown.values().sum())
+    }
+
+    pub fn contained_breakdown(&self, bag: &str) -> Option<BTreeMap<&str, BigUint>> {
+        let order = self.topological_order(bag)?;
+        let mut bag_counts = vec![BigUint::zero(); self.names.len()];
+        bag_counts[order[0]] = One::one();
+        for &id in &order {
+            let bag_count = bag_counts[id].clone();
+            for (child, cost) in self.children.edges(id) {
+                bag_counts[child] += &bag_count * cost;
+            }
+        }
+        Some(order[1..].iter().map(|&id| (self.name(id), std::mem::take(&mut bag_counts[id]))).collect())
+    }
+
+    // bag and its descendants ordered so that every bag comes before the bags it contains
+    fn topological_order(&self, bag: &str) -> Option<Vec<usize>> {
+        let start = self.id(bag)?;
+        let mut order = Vec::new();
+        let mut visited = vec![false; self.names.len()];
+        visited[start] = true;
+        let mut stack = vec![(start, 0)];
+        while let Some((id, edge_idx)) = stack.pop() {
+            match self.children.targets(id).get(edge_idx) {
+                Some(&child) => {
+                    stack.push((id, edge_idx + 1));
+                    if !visited[child] {
+                        visited[child] = true;
+                        stack.push((child, 0));
+                    }
+                },
+                None => order.push(id)
+            }
+        }
+        order.reverse();
+        Some(order)
+    }
+
+    pub fn paths(&self, from: &str, to: &str) -> Vec<BagPath<'_>> {
+        let mut paths = Vec::new();
+        if let (Some(from), Some(to)) = (self.id(from), self.id(to)) {
+            let mut path = vec![from];
+            let mut on_path = vec![false; self.names.len()];
+            on_path[from] = true;
+            self.collect_paths(to, &mut path, &mut on_path, One::one(), &mut paths);
+        }
+        paths
+    }
+
+    fn collect_paths<'a>(&'a self, to: usize, path: &mut Vec<usize>, on_path: &mut Vec<bool>, multiplicity: BigUint, paths: &mut Vec<BagPath<'a>>) {
+        let current = *path.last().unwrap();
+        if current == to && path.len() > 1 {
+            paths.push(BagPath { bags: path.iter().map(|&id| self.name(id)).collect(), multiplicity });
+            return;
+        }
+        for (child, cost) in self.children.edges(current) {
+            if on_path[child] {
+                continue;
+            }
+            on_path[child] = true;
+            path.push(child);
+            self.collect_paths(to, path, on_path, &multiplicity * cost, paths);
+            path.pop();
+            on_path[child] = false;
         }
     }
 }
 
+#[derive(Default)]
+struct BagGraphBuilder {
+    names: Vec<Rc<str>>,
+    ids: HashMap<Rc<str>, usize>,
+    edges: Vec<(usize, usize, usize)>,
+    rule_line_numbers: Vec<Vec<usize>>
+}
 
+impl BagGraphBuilder {
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        let name: Rc<str> = Rc::from(name);
+        self.names.push(Rc::clone(&name));
+        self.ids.insert(name, id);
+        self.rule_line_numbers.push(Vec::new());
+        id
+    }
 
-pub fn solve(input: &File, counter: &impl Counter) -> Option<usize> {
-    const TARGET_NAME: &str = "shiny gold";
-    let nodes = parse_input(input);
-    counter.count(&nodes, TARGET_NAME)
+    fn build(self) -> BagGraph {
+        let reversed_edges: Vec<(usize, usize, usize)> = self.edges.iter().map(|&(parent, child, cost)| (child, parent, cost)).collect();
+        BagGraph {
+            children: Adjacency::new(self.names.len(), &self.edges),
+            parents: Adjacency::new(self.names.len(), &reversed_edges),
+            names: self.names,
+            ids: self.ids,
+            rule_line_numbers: self.rule_line_numbers
+        }
+    }
 }
 
-fn parse_input(input: &File) -> HashMap<String, Node> {
+pub fn solve(input: &File, counter: &impl Counter, target: &str) -> Result<Option<BigUint>, Vec<RuleError>> {
+    let graph = BagGraph::parse(input)?;
+    Ok(counter.count(&graph, target))
+}
+
+fn parse_input(input: &File) -> Result<BagGraph, Vec<RuleError>> {
     let child_pattern = Regex::new(r"(?P<cost>[[:digit:]]+) (?P<node>.+) bags?").unwrap();
     let line_pattern = Regex::new(r"^(?P<node>.+) bags contain (?:no other bags|(?P<children>.*)+)\.$").unwrap();
 
-    let mut nodes = HashMap::new();
-    for line in BufReader::new(input).lines() {
+    let mut builder = BagGraphBuilder::default();
+    let mut errors = Vec::new();
+    for (line_idx, line) in BufReader::new(input).lines().enumerate() {
         let line = line.unwrap();
-        let cap = line_pattern.captures(&line).unwrap();
-        let node_name = &cap["node"];
-        nodes.entry(node_name.to_owned()).or_insert(Node::new());
+        let malformed_rule = || RuleError::MalformedRule { line_number: line_idx + 1, line: line.clone() };
+        let cap = match line_pattern.captures(&line) {
+            Some(cap) => cap,
+            None => {
+                errors.push(malformed_rule());
+                continue;
+            }
+        };
+        let node_id = builder.intern(&cap["node"]);
+        builder.rule_line_numbers[node_id].push(line_idx + 1);
         if let Some(children_group) = cap.name("children") {
             let children: Vec<&str> = children_group.as_str().split(", ").collect();
             for child in children {
-                let child = child_pattern.captures(child).unwrap();
-                let child_node_name = &child["node"];
-                let cost = child["cost"].parse().unwrap();
-                let child_node = nodes.entry(child_node_name.to_owned()).or_insert(Node::new());
-                child_node.parents.push(Edge::new(node_name, cost));
-                nodes.entry(node_name.to_owned()).and_modify(|node| node.children.push(Edge::new(child_node_name, cost)));
+                let child = match child_pattern.captures(child) {
+                    Some(child) => child,
+                    None => {
+                        errors.push(malformed_rule());
+                        break;
+                    }
+                };
+                let cost = match child["cost"].parse() {
+                    Ok(cost) => cost,
+                    Err(_) => {
+                        errors.push(malformed_rule());
+                        break;
+                    }
+                };
+                let child_node_id = builder.intern(&child["node"]);
+                builder.edges.push((node_id, child_node_id, cost));
             }
         }
     };
-    nodes
+    if errors.is_empty() {
+        Ok(builder.build())
+    } else {
+        Err(errors)
+    }
 }
@@ -0,0 +1,66 @@
+use std::fmt;
+use super::repair::{self, Repair};
+use super::vm::{Instruction, InstructionSet};
+use crate::graph;
+
+pub struct Analysis {
+    pub unreachable: Vec<usize>,
+    pub loops: Vec<Vec<usize>>,
+    pub repairs: Vec<Repair>
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |indices: &[usize]| indices.iter().map(|index| index.to_string()).collect::<Vec<String>>().join(", ");
+        write!(f, "\nUnreachable instructions: {}", join(&self.unreachable))?;
+        for instructions in &self.loops {
+            write!(f, "\nLoop: {}", join(instructions))?;
+        }
+        for repair in &self.repairs {
+            write!(f, "\nRepair: {}", repair)?;
+        }
+        Ok(())
+    }
+}
+
+fn control_flow_graph(instruction_set: &InstructionSet, program: &[Instruction]) -> Vec<Vec<usize>> {
+    program.iter().enumerate().map(|(index, instruction)| {
+        instruction_set.successors(instruction, index).into_iter()
+            .filter(|&successor| successor >= 0 && (successor as usize) < program.len())
+            .map(|successor| successor as usize)
+            .collect()
+    }).collect()
+}
+
+fn find_unreachable(successors: &[Vec<usize>]) -> Vec<usize> {
+    let mut reachable = vec![false; successors.len()];
+    let mut stack = Vec::new();
+    if !successors.is_empty() {
+        reachable[0] = true;
+        stack.push(0);
+    }
+    while let Some(index) = stack.pop() {
+        for &successor in &successors[index] {
+            if !reachable[successor] {
+                reachable[successor] = true;
+                stack.push(successor);
+            }
+        }
+    }
+    (0..successors.len()).filter(|&index| !reachable[index]).collect()
+}
+
+fn find_loops(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut loops = graph::find_cycles(successors.len(), 0..successors.len(), |index| successors[index].as_slice());
+    loops.sort_unstable();
+    loops
+}
+
+pub fn analyse(instruction_set: &InstructionSet, program: &[Instruction]) -> Analysis {
+    let successors = control_flow_graph(instruction_set, program);
+    Analysis {
+        unreachable: find_unreachable(&successors),
+        loops: find_loops(&successors),
+        repairs: repair::single_flip_repairs(instruction_set, program).collect()
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::{BTreeSet, HashMap};
+use super::ParseError;
+use super::vm::{self, Instruction, InstructionSet};
+
+const END_LABEL: &str = "end";
+
+fn is_label(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn label_name(target: usize, program: &[Instruction]) -> String {
+    if target == program.len() {
+        END_LABEL.to_owned()
+    } else {
+        format!("L{}", target)
+    }
+}
+
+// assembles lines of instructions where jump offsets can be given as labels, defined by "label:" in front of an instruction
+pub fn assemble(instruction_set: &InstructionSet, lines: &[String]) -> Result<Vec<Instruction>, Vec<ParseError>> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    for (line_idx, line) in lines.iter().enumerate() {
+        let mut code = line.split(';').next().unwrap_or_default().trim();
+        while let Some((label, rest)) = code.split_once(':').filter(|(label, _)| is_label(label.trim())) {
+            let label = label.trim();
+            if labels.insert(label, statements.len()).is_some() {
+                errors.push(ParseError { line_number: line_idx + 1, error: vm::ParseError::DuplicateLabel(label.to_owned()) });
+            }
+            code = rest.trim();
+        }
+        if !code.is_empty() {
+            statements.push((line_idx + 1, code));
+        }
+    }
+
+    let mut program = Vec::with_capacity(statements.len());
+    for (index, &(line_number, code)) in statements.iter().enumerate() {
+        let mut tokens: Vec<String> = code.split_whitespace().map(|token| token.to_owned()).collect();
+        let jump_operand = instruction_set.opcode(&tokens[0])
+            .and_then(|opcode| instruction_set.definition(opcode).control_flow.jump_operand());
+        if let Some(token) = jump_operand.and_then(|operand| tokens.get_mut(operand + 1)).filter(|token| is_label(token)) {
+            match labels.get(token.as_str()) {
+                Some(&target) => *token = format!("{:+}", target as isize - index as isize),
+                None => {
+                    errors.push(ParseError { line_number, error: vm::ParseError::UnknownLabel(token.to_owned()) });
+                    continue;
+                }
+            }
+        }
+        match instruction_set.parse(&tokens.join(" ")) {
+            Ok(instruction) => program.push(instruction),
+            Err(error) => errors.push(ParseError { line_number, error })
+        }
+    }
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        errors.sort_by_key(|error| error.line_number);
+        Err(errors)
+    }
+}
+
+pub fn disassemble(instruction_set: &InstructionSet, program: &[Instruction]) -> String {
+    let in_program = |target: isize| target >= 0 && target as usize <= program.len();
+    let targets: BTreeSet<usize> = program.iter().enumerate()
+        .filter_map(|(index, instruction)| instruction_set.jump_target(instruction, index))
+        .filter(|&target| in_program(target))
+        .map(|target| target as usize)
+        .collect();
+    let label_width = targets.iter().map(|&target| label_name(target, program).len() + 2).max().unwrap_or(0);
+
+    let mut lines = Vec::with_capacity(program.len() + 1);
+    for (index, instruction) in program.iter().enumerate() {
+        let label = if targets.contains(&index) { format!("{}:", label_name(index, program)) } else { String::new() };
+        let mut parts = vec![instruction_set.definition(instruction.opcode).name.clone()];
+        parts.extend(instruction.operands.iter().map(|operand| operand.to_string()));
+        let jump_operand = instruction_set.definition(instruction.opcode).control_flow.jump_operand();
+        if let (Some(operand), Some(target)) = (jump_operand, instruction_set.jump_target(instruction, index)) {
+            if in_program(target) {
+                parts[operand + 1] = label_name(target as usize, program);
+            }
+        }
+        lines.push(format!("{:<width$}{}", label, parts.join(" "), width = label_width));
+    }
+    if targets.contains(&program.len()) {
+        lines.push(format!("{}:", END_LABEL));
+    }
+    lines.join("\n")
+}
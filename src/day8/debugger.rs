@@ -0,0 +1,183 @@
+use std::io;
+use std::io::prelude::*;
+use std::fmt;
+use super::vm::{CPU, ExecutionResult};
+
+const HELP: &str = "commands:
+  step [count]             execute the next instruction(s)
+  continue                 run until a breakpoint is hit or the program halts
+  break ic <index>         stop before the instruction at index is executed
+  break acc <op> <value>   stop when the accumulator compares to value (op is <, = or >)
+  breakpoints              list breakpoints
+  delete <number>          delete a breakpoint
+  inspect                  show the instruction counter, registers and next instruction
+  trace [count]            show the last executed instructions
+  quit                     stop debugging";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    Instruction(usize),
+    AccumulatorBelow(isize),
+    AccumulatorEquals(isize),
+    AccumulatorAbove(isize)
+}
+
+impl Breakpoint {
+    fn parse(args: &[&str]) -> Option<Breakpoint> {
+        match args {
+            ["ic", index] => index.parse().ok().map(Breakpoint::Instruction),
+            ["acc", op, value] => {
+                let value = value.parse().ok()?;
+                match *op {
+                    "<" => Some(Breakpoint::AccumulatorBelow(value)),
+                    "=" => Some(Breakpoint::AccumulatorEquals(value)),
+                    ">" => Some(Breakpoint::AccumulatorAbove(value)),
+                    _ => None
+                }
+            },
+            _ => None
+        }
+    }
+
+    fn is_hit(&self, cpu: &CPU) -> bool {
+        match *self {
+            Breakpoint::Instruction(index) => cpu.ic == index as isize,
+            Breakpoint::AccumulatorBelow(value) => cpu.accumulator() < value,
+            Breakpoint::AccumulatorEquals(value) => cpu.accumulator() == value,
+            Breakpoint::AccumulatorAbove(value) => cpu.accumulator() > value
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Instruction(index) => write!(f, "ic {}", index),
+            Breakpoint::AccumulatorBelow(value) => write!(f, "acc < {}", value),
+            Breakpoint::AccumulatorEquals(value) => write!(f, "acc = {}", value),
+            Breakpoint::AccumulatorAbove(value) => write!(f, "acc > {}", value)
+        }
+    }
+}
+
+pub enum Stop {
+    Breakpoint(Breakpoint),
+    Halted(ExecutionResult)
+}
+
+pub struct Debugger<'a> {
+    pub cpu: CPU<'a>,
+    pub breakpoints: Vec<Breakpoint>,
+    halted: Option<ExecutionResult>
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(mut cpu: CPU<'a>) -> Self {
+        cpu.enable_trace();
+        Debugger { cpu, breakpoints: Vec::new(), halted: None }
+    }
+
+    pub fn step(&mut self) -> Option<ExecutionResult> {
+        if self.halted.is_none() {
+            self.halted = self.cpu.step();
+        }
+        self.halted
+    }
+
+    pub fn resume(&mut self) -> Stop {
+        loop {
+            if let Some(result) = self.step() {
+                return Stop::Halted(result);
+            }
+            if let Some(&breakpoint) = self.breakpoints.iter().find(|breakpoint| breakpoint.is_hit(&self.cpu)) {
+                return Stop::Breakpoint(breakpoint);
+            }
+        }
+    }
+
+    fn inspect(&self, output: &mut impl Write) -> io::Result<()> {
+        writeln!(output, "ic {}, registers {:?}, steps {}", self.cpu.ic, self.cpu.registers, self.cpu.steps)?;
+        let program = self.cpu.program();
+        match program.get(self.cpu.ic as usize).filter(|_| self.cpu.ic >= 0) {
+            Some(instruction) => writeln!(output, "next {} (visited {} times)",
+                self.cpu.instruction_set().format(instruction), self.cpu.visited_instruction_count[self.cpu.ic as usize]),
+            None => writeln!(output, "next instruction is outside of the program")
+        }
+    }
+
+    fn print_trace(&self, count: usize, output: &mut impl Write) -> io::Result<()> {
+        let trace = self.cpu.trace.as_deref().unwrap_or_default();
+        for entry in &trace[trace.len().saturating_sub(count)..] {
+            writeln!(output, "{}", self.cpu.format_trace_entry(entry))?;
+        }
+        Ok(())
+    }
+
+    fn print_stop(&self, stop: Stop, output: &mut impl Write) -> io::Result<()> {
+        match stop {
+            Stop::Breakpoint(breakpoint) => writeln!(output, "stopped at breakpoint {}", breakpoint)?,
+            Stop::Halted(result) => writeln!(output, "program halted: {}", result)?
+        }
+        self.inspect(output)
+    }
+
+    pub fn repl(&mut self, commands: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        write!(output, "(debug) ")?;
+        output.flush()?;
+        for command in commands.lines() {
+            let command = command?;
+            let args: Vec<&str> = command.split_whitespace().collect();
+            match args.as_slice() {
+                [] => {},
+                ["step"] | ["s"] => match self.step() {
+                    Some(result) => self.print_stop(Stop::Halted(result), output)?,
+                    None => self.print_trace(1, output)?
+                },
+                ["step", count] | ["s", count] => match count.parse::<usize>() {
+                    Ok(count) => {
+                        for _ in 0..count {
+                            if self.step().is_some() {
+                                break;
+                            }
+                        }
+                        self.inspect(output)?;
+                    },
+                    Err(_) => writeln!(output, "invalid step count {}", count)?
+                },
+                ["continue"] | ["c"] => {
+                    let stop = self.resume();
+                    self.print_stop(stop, output)?;
+                },
+                ["break", breakpoint @ ..] | ["b", breakpoint @ ..] => match Breakpoint::parse(breakpoint) {
+                    Some(breakpoint) => {
+                        self.breakpoints.push(breakpoint);
+                        writeln!(output, "breakpoint {}: {}", self.breakpoints.len(), breakpoint)?;
+                    },
+                    None => writeln!(output, "invalid breakpoint, see help")?
+                },
+                ["breakpoints"] => {
+                    for (i, breakpoint) in self.breakpoints.iter().enumerate() {
+                        writeln!(output, "breakpoint {}: {}", i + 1, breakpoint)?;
+                    }
+                },
+                ["delete", number] | ["d", number] => match number.parse::<usize>() {
+                    Ok(number) if number >= 1 && number <= self.breakpoints.len() => {
+                        self.breakpoints.remove(number - 1);
+                    },
+                    _ => writeln!(output, "no breakpoint {}", number)?
+                },
+                ["inspect"] | ["i"] => self.inspect(output)?,
+                ["trace"] | ["t"] => self.print_trace(usize::MAX, output)?,
+                ["trace", count] | ["t", count] => match count.parse() {
+                    Ok(count) => self.print_trace(count, output)?,
+                    Err(_) => writeln!(output, "invalid trace count {}", count)?
+                },
+                ["quit"] | ["q"] => return Ok(()),
+                _ => writeln!(output, "{}", HELP)?
+            }
+            write!(output, "(debug) ")?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+}
@@ -1,125 +1,125 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::fmt;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum Instruction {
-    NOP(isize),
-    ACC(isize),
-    JMP(isize),
-    END(ExecutionResult)
-}
+pub mod vm;
+mod analysis;
+mod assembler;
+mod debugger;
+mod repair;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum ExecutionResult {
-    SUCCESS,
-    ERROR
-}
+pub use repair::{EditKind, RepairSearch};
 
-impl Instruction {
-    fn parse(input: &str) -> Instruction {
-        let mut parts = input.split_whitespace();
-        let instruction = parts.next().unwrap();
-        let amount = parts.next().unwrap().replace("+", "").parse().unwrap();
-        match instruction {
-            "nop" => Instruction::NOP(amount),
-            "acc" => Instruction::ACC(amount),
-            "jmp" => Instruction::JMP(amount),
-            _ => panic!("Unknown instruction {}", instruction)
-        }
-    }
-}
+use vm::{CPU, ExecutionResult, HaltConditions, Instruction, InstructionSet};
+use debugger::Debugger;
 
-struct CPU {
-    ic: isize,
-    accumulator: isize,
-    visited_instruction_count: Vec<usize>
+#[derive(Debug)]
+pub struct ParseError {
+    line_number: usize,
+    error: vm::ParseError
 }
 
-impl CPU {
-
-    fn execute(program: &[Instruction]) -> (CPU, ExecutionResult) {
-        let mut cpu = CPU {
-            ic: 0,
-            accumulator: 0,
-            visited_instruction_count: vec![0; program.len()]
-        };
-        let result = cpu.run(program);
-        (cpu, result)
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line_number, self.error)
     }
+}
 
-    fn get_instruction(&mut self, program: &[Instruction]) -> Instruction {
-        let instruction_index = self.ic as usize;
-        if instruction_index == program.len() {
-            return Instruction::END(ExecutionResult::SUCCESS);
-        }
-        let exec_count = self.visited_instruction_count.get_mut(instruction_index).unwrap();
-        *exec_count += 1;
-        if *exec_count >= 2 {
-            Instruction::END(ExecutionResult::ERROR)
-        } else {
-            program.get(instruction_index).unwrap().to_owned()
-        }
+pub fn solve(input: &File, fix_program: bool) -> Result<Option<isize>, Vec<ParseError>> {
+    let instruction_set = InstructionSet::handheld();
+    let program = parse_input(input, &instruction_set)?;
+    if fix_program {
+        Ok(repair::find_repair(&instruction_set, &program).map(|repair| repair.accumulator))
+    } else {
+        Ok(Some(CPU::execute(&instruction_set, &program).0.accumulator()))
     }
+}
 
-    fn run(&mut self, program: &[Instruction]) -> ExecutionResult {
-        loop {
-            if self.ic < 0 {
-                panic!("Negative IC ({})", self.ic)
-            }
-            let instruction = self.get_instruction(&program);
-            match instruction {
-                Instruction::NOP(_amount) => {
-                    self.ic += 1;
-                },
-                Instruction::ACC(amount) => {
-                    self.accumulator += amount;
-                    self.ic += 1;
-                },
-                Instruction::JMP(amount) => {
-                    self.ic += amount;
-                },
-                Instruction::END(result) => {
-                    return result
-                }
-            }
-        }
-    }
+pub fn find_repair(input: &File) -> Result<Option<repair::Repair>, Vec<ParseError>> {
+    let instruction_set = InstructionSet::handheld();
+    let program = parse_input(input, &instruction_set)?;
+    Ok(repair::find_repair(&instruction_set, &program))
 }
 
-fn find_successful_program(broken_program: &[Instruction]) -> Option<Vec<Instruction>> {
-    let program = broken_program.to_owned();
-    let (_, result) = CPU::execute(&program);
-    if result == ExecutionResult::SUCCESS {
-        return Some(program);
-    }
-    for i in 0..program.len() {
-        let old_instruction = program.get(i).unwrap();
-        let new_instruction = match old_instruction {
-            Instruction::NOP(amount) => Instruction::JMP(*amount),
-            Instruction::JMP(amount) => Instruction::NOP(*amount),
-            _ => continue
-        };
-        let mut new_program = program.clone();
-        new_program[i] = new_instruction;
-        let (_, result) = CPU::execute(&new_program);
-        if result == ExecutionResult::SUCCESS {
-            return Some(new_program);
+pub struct ExecutionSummary {
+    pub result: ExecutionResult,
+    pub halting_instruction: Option<String>,
+    pub registers: Vec<isize>,
+    pub steps: usize
+}
+
+impl fmt::Display for ExecutionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.result)?;
+        if let Some(instruction) = &self.halting_instruction {
+            write!(f, " ({})", instruction)?;
         }
+        write!(f, " after {} steps with registers {:?}", self.steps, self.registers)
     }
-    None
 }
 
-pub fn solve(input: &File, fix_program: bool) -> Option<isize> {
-    let program = parse_input(input);
-    let runnable_program = if fix_program {
-        find_successful_program(&program)
+fn load<'a>(instruction_set: &'a InstructionSet, program: &'a [Instruction]) -> CPU<'a> {
+    const REGISTER_PROGRAM_STEP_LIMIT: usize = 10_000_000;
+    let register_count = vm::register_count(program);
+    // programs using registers may legitimately revisit instructions, so only their step count is limited
+    let halt_conditions = if register_count > 1 {
+        HaltConditions { max_instruction_visits: None, max_steps: Some(REGISTER_PROGRAM_STEP_LIMIT) }
     } else {
-        Some(program)
+        HaltConditions::default()
+    };
+    CPU::new(instruction_set, program, register_count, halt_conditions)
+}
+
+pub fn execution_summary(input: &File) -> Result<Option<ExecutionSummary>, Vec<ParseError>> {
+    let instruction_set = InstructionSet::with_registers();
+    let program = parse_input(input, &instruction_set)?;
+    let mut cpu = load(&instruction_set, &program);
+    let result = cpu.run();
+    let halting_instruction = match result {
+        ExecutionResult::InfiniteLoop { ic } => Some(instruction_set.format(&program[ic])),
+        _ => None
     };
-    runnable_program.and_then(|program| Some(CPU::execute(&program).0.accumulator))
+    Ok(Some(ExecutionSummary { result, halting_instruction, registers: cpu.registers, steps: cpu.steps }))
+}
+
+pub fn execution_trace(input: &File) -> Result<Option<String>, Vec<ParseError>> {
+    let instruction_set = InstructionSet::with_registers();
+    let program = parse_input(input, &instruction_set)?;
+    let mut cpu = load(&instruction_set, &program);
+    cpu.enable_trace();
+    let result = cpu.run();
+    let mut lines: Vec<String> = cpu.trace.iter().flatten().map(|entry| cpu.format_trace_entry(entry)).collect();
+    lines.push(format!("{}", result));
+    Ok(Some(format!("\n{}", lines.join("\n"))))
+}
+
+pub fn debug(input: &File, commands: impl BufRead, output: &mut impl Write) -> Result<(), Vec<ParseError>> {
+    let instruction_set = InstructionSet::with_registers();
+    let program = parse_input(input, &instruction_set)?;
+    Debugger::new(load(&instruction_set, &program)).repl(commands, output).unwrap();
+    Ok(())
+}
+
+pub fn search_repairs(input: &File, search: &RepairSearch) -> Result<Option<repair::RepairSearchResult>, Vec<ParseError>> {
+    let instruction_set = InstructionSet::handheld();
+    let program = parse_input(input, &instruction_set)?;
+    Ok(Some(repair::search_repairs(&instruction_set, &program, search)))
+}
+
+pub fn disassemble(input: &File) -> Result<Option<String>, Vec<ParseError>> {
+    let instruction_set = InstructionSet::with_registers();
+    let program = parse_input(input, &instruction_set)?;
+    Ok(Some(format!("\n{}", assembler::disassemble(&instruction_set, &program))))
+}
+
+pub fn analyse(input: &File) -> Result<Option<analysis::Analysis>, Vec<ParseError>> {
+    let instruction_set = InstructionSet::handheld();
+    let program = parse_input(input, &instruction_set)?;
+    Ok(Some(analysis::analyse(&instruction_set, &program)))
 }
 
-fn parse_input(input: &File) -> Vec<Instruction> {
-    BufReader::new(input).lines().map(|line| Instruction::parse(&line.unwrap())).collect()
+fn parse_input(input: &File, instruction_set: &InstructionSet) -> Result<Vec<Instruction>, Vec<ParseError>> {
+    let lines: Vec<String> = BufReader::new(input).lines().map(|line| line.unwrap()).collect();
+    assembler::assemble(instruction_set, &lines)
 }
@@ -0,0 +1,246 @@
+use std::fmt;
+use std::collections::{BTreeSet, HashSet};
+use super::vm::{CPU, ExecutionResult, HaltConditions, Instruction, InstructionSet, Opcode, Operand};
+
+pub struct Change {
+    pub index: usize,
+    pub before: String,
+    pub after: String
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "changed instruction {} from {} to {}", self.index, self.before, self.after)
+    }
+}
+
+pub struct Repair {
+    pub changes: Vec<Change>,
+    pub accumulator: isize
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            write!(f, "no changes needed")?;
+        }
+        let changes: Vec<String> = self.changes.iter().map(|change| change.to_string()).collect();
+        write!(f, "{}, accumulator {}", changes.join(", "), self.accumulator)
+    }
+}
+
+pub fn flipped_opcode(instruction_set: &InstructionSet, opcode: Opcode) -> Option<Opcode> {
+    let nop = instruction_set.opcode("nop")?;
+    let jmp = instruction_set.opcode("jmp")?;
+    if opcode == nop {
+        Some(jmp)
+    } else if opcode == jmp {
+        Some(nop)
+    } else {
+        None
+    }
+}
+
+fn is_in_program(index: isize, program: &[Instruction]) -> bool {
+    index >= 0 && (index as usize) <= program.len()
+}
+
+// instructions from which the program reaches its end, the end itself being at index program.len()
+pub fn find_terminating_instructions(instruction_set: &InstructionSet, program: &[Instruction]) -> Vec<bool> {
+    let mut predecessors = vec![Vec::new(); program.len() + 1];
+    for (index, instruction) in program.iter().enumerate() {
+        for successor in instruction_set.successors(instruction, index) {
+            if is_in_program(successor, program) {
+                predecessors[successor as usize].push(index);
+            }
+        }
+    }
+    let mut reaches_end = vec![false; program.len() + 1];
+    reaches_end[program.len()] = true;
+    let mut stack = vec![program.len()];
+    while let Some(index) = stack.pop() {
+        for &predecessor in &predecessors[index] {
+            if !reaches_end[predecessor] {
+                reaches_end[predecessor] = true;
+                stack.push(predecessor);
+            }
+        }
+    }
+    reaches_end
+}
+
+// flips of a single instruction on the execution path after which the program terminates
+pub fn single_flip_repairs<'a>(instruction_set: &'a InstructionSet, program: &'a [Instruction]) -> impl Iterator<Item = Repair> + 'a {
+    let mut cpu = CPU::new(instruction_set, program, 1, HaltConditions::default());
+    cpu.enable_trace();
+    cpu.run();
+    let reaches_end = find_terminating_instructions(instruction_set, program);
+    cpu.trace.take().unwrap_or_default().into_iter().filter_map(move |entry| {
+        let new_opcode = flipped_opcode(instruction_set, entry.instruction.opcode)?;
+        let new_instruction = Instruction { opcode: new_opcode, operands: entry.instruction.operands.clone() };
+        let lands_in_terminating_set = instruction_set.successors(&new_instruction, entry.ic).iter()
+            .all(|&successor| is_in_program(successor, program) && reaches_end[successor as usize]);
+        if !lands_in_terminating_set {
+            return None;
+        }
+        let mut repaired_program = program.to_owned();
+        repaired_program[entry.ic] = new_instruction;
+        let (repaired_cpu, result) = CPU::execute(instruction_set, &repaired_program);
+        if result != ExecutionResult::Terminated {
+            return None;
+        }
+        let change = Change {
+            index: entry.ic,
+            before: instruction_set.format(&entry.instruction),
+            after: instruction_set.format(&repaired_program[entry.ic])
+        };
+        Some(Repair { changes: vec![change], accumulator: repaired_cpu.accumulator() })
+    })
+}
+
+pub fn find_repair(instruction_set: &InstructionSet, program: &[Instruction]) -> Option<Repair> {
+    let (cpu, result) = CPU::execute(instruction_set, program);
+    if result == ExecutionResult::Terminated {
+        return Some(Repair { changes: Vec::new(), accumulator: cpu.accumulator() });
+    }
+    single_flip_repairs(instruction_set, program).next()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum EditKind {
+    FlipJump,
+    NegateAccumulation,
+    Delete
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+struct Edit {
+    index: usize,
+    kind: EditKind
+}
+
+pub struct RepairSearch {
+    pub max_edits: usize,
+    pub edit_kinds: Vec<EditKind>,
+    pub execution_budget: usize
+}
+
+pub struct RepairSearchResult {
+    pub repairs: Vec<Repair>,
+    pub budget_exhausted: bool
+}
+
+impl fmt::Display for RepairSearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for repair in &self.repairs {
+            write!(f, "\n{}", repair)?;
+        }
+        if self.budget_exhausted {
+            write!(f, "\nsearch budget exhausted")?;
+        }
+        Ok(())
+    }
+}
+
+// the edited program and the original index of each of its instructions
+fn apply_edits(instruction_set: &InstructionSet, program: &[Instruction], edits: &[Edit]) -> Option<(Vec<Instruction>, Vec<usize>)> {
+    let acc = instruction_set.opcode("acc");
+    let mut edited_program = Vec::with_capacity(program.len());
+    let mut origins = Vec::with_capacity(program.len());
+    let mut edits = edits.iter().peekable();
+    for (index, instruction) in program.iter().enumerate() {
+        let mut instruction = instruction.clone();
+        if let Some(edit) = edits.next_if(|edit| edit.index == index) {
+            match edit.kind {
+                EditKind::FlipJump => instruction.opcode = flipped_opcode(instruction_set, instruction.opcode)?,
+                EditKind::NegateAccumulation => match instruction.operands.as_mut_slice() {
+                    [Operand::Value(amount)] if Some(instruction.opcode) == acc && *amount != 0 => *amount = amount.checked_neg()?,
+                    _ => return None
+                },
+                EditKind::Delete => continue
+            }
+        }
+        edited_program.push(instruction);
+        origins.push(index);
+    }
+    Some((edited_program, origins))
+}
+
+fn describe_repair(instruction_set: &InstructionSet, program: &[Instruction], edits: &[Edit], accumulator: isize) -> Repair {
+    let edited_program = apply_edits(instruction_set, program, edits).unwrap().0;
+    let mut edited_instructions = edited_program.iter();
+    let mut edits = edits.iter().peekable();
+    let mut changes = Vec::new();
+    for (index, instruction) in program.iter().enumerate() {
+        let edit = edits.next_if(|edit| edit.index == index);
+        let edited_instruction = match edit {
+            Some(Edit { kind: EditKind::Delete, .. }) => None,
+            _ => edited_instructions.next()
+        };
+        if edit.is_some() {
+            changes.push(Change {
+                index,
+                before: instruction_set.format(instruction),
+                after: edited_instruction.map_or_else(|| "nothing".to_owned(), |instruction| instruction_set.format(instruction))
+            });
+        }
+    }
+    Repair { changes, accumulator }
+}
+
+// breadth-first search over sets of edits, an edit is only tried on instructions that the partially repaired program executes or jumps over
+pub fn search_repairs(instruction_set: &InstructionSet, program: &[Instruction], search: &RepairSearch) -> RepairSearchResult {
+    let mut executions = 0;
+    let mut seen = HashSet::new();
+    let mut level: Vec<Vec<Edit>> = vec![Vec::new()];
+    for edit_count in 0..=search.max_edits {
+        let mut repairs = Vec::new();
+        let mut next_level = Vec::new();
+        for edits in &level {
+            if executions >= search.execution_budget {
+                return RepairSearchResult { repairs, budget_exhausted: true };
+            }
+            executions += 1;
+            let (edited_program, origins) = apply_edits(instruction_set, program, edits).unwrap();
+            let mut cpu = CPU::new(instruction_set, &edited_program, 1, HaltConditions::default());
+            cpu.enable_trace();
+            if cpu.run() == ExecutionResult::Terminated {
+                repairs.push(describe_repair(instruction_set, program, edits, cpu.accumulator()));
+                continue;
+            }
+            if edit_count == search.max_edits {
+                continue;
+            }
+            let trace = cpu.trace.take().unwrap_or_default();
+            let executed: BTreeSet<usize> = trace.iter().map(|entry| origins[entry.ic]).collect();
+            // deleting an instruction that never runs still moves the target of every jump across it
+            let mut jumped_over = BTreeSet::new();
+            for entry in &trace {
+                if let Some(target) = instruction_set.jump_target(&edited_program[entry.ic], entry.ic) {
+                    let target = target.clamp(0, edited_program.len() as isize - 1) as usize;
+                    let crossed = if target > entry.ic { entry.ic + 1..=target } else { target..=entry.ic.saturating_sub(1) };
+                    jumped_over.extend(crossed.filter(|&idx| idx != entry.ic).map(|idx| origins[idx]));
+                }
+            }
+            for &kind in &search.edit_kinds {
+                let candidates = if kind == EditKind::Delete { &executed | &jumped_over } else { executed.clone() };
+                for index in candidates {
+                    if edits.iter().any(|edit| edit.index == index) {
+                        continue;
+                    }
+                    let mut new_edits = edits.clone();
+                    new_edits.push(Edit { index, kind });
+                    new_edits.sort_unstable();
+                    if apply_edits(instruction_set, program, &new_edits).is_some() && seen.insert(new_edits.clone()) {
+                        next_level.push(new_edits);
+                    }
+                }
+            }
+        }
+        if !repairs.is_empty() {
+            return RepairSearchResult { repairs, budget_exhausted: false };
+        }
+        level = next_level;
+    }
+    RepairSearchResult { repairs: Vec::new(), budget_exhausted: false }
+}
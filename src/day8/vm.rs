@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use std::fmt;
+
+pub const ACCUMULATOR: usize = 0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OperandType {
+    Value,
+    Register
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Operand {
+    Value(isize),
+    Register(usize)
+}
+
+impl Operand {
+    fn parse(input: &str, operand_type: OperandType, register_limit: usize) -> Option<Operand> {
+        match operand_type {
+            OperandType::Value => input.parse().ok().map(Operand::Value),
+            OperandType::Register => input.strip_prefix('r').and_then(|register| register.parse().ok())
+                .filter(|&register| register < register_limit)
+                .map(Operand::Register)
+        }
+    }
+
+    pub fn value(&self, registers: &[isize]) -> isize {
+        match *self {
+            Operand::Value(value) => value,
+            Operand::Register(register) => registers[register]
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Value(value) => write!(f, "{:+}", value),
+            Operand::Register(register) => write!(f, "r{}", register)
+        }
+    }
+}
+
+// executes the instruction on the registers and returns the offset to the next instruction, or None on arithmetic overflow
+pub type Execute = fn(&mut [isize], &[Operand]) -> Option<isize>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ControlFlow {
+    Next,
+    Jump { operand: usize },
+    Branch { operand: usize }
+}
+
+impl ControlFlow {
+    pub fn jump_operand(&self) -> Option<usize> {
+        match *self {
+            ControlFlow::Next => None,
+            ControlFlow::Jump { operand } | ControlFlow::Branch { operand } => Some(operand)
+        }
+    }
+}
+
+pub struct OpcodeDefinition {
+    pub name: String,
+    pub operand_types: Vec<OperandType>,
+    pub control_flow: ControlFlow,
+    pub execute: Execute
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Opcode(usize);
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub operands: Vec<Operand>
+}
+
+pub fn register_count(program: &[Instruction]) -> usize {
+    program.iter().flat_map(|instruction| &instruction.operands)
+        .filter_map(|operand| match operand {
+            Operand::Register(register) => Some(register + 1),
+            Operand::Value(_) => None
+        })
+        .fold(ACCUMULATOR + 1, usize::max)
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    UnknownOpcode(String),
+    WrongOperandCount { opcode: String, expected: usize, actual: usize },
+    InvalidOperand { opcode: String, operand: String },
+    UnknownLabel(String),
+    DuplicateLabel(String)
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnknownOpcode(opcode) => write!(f, "unknown instruction {}", opcode),
+            ParseError::WrongOperandCount { opcode, expected, actual } =>
+                write!(f, "{} expects {} operands, but got {}", opcode, expected, actual),
+            ParseError::InvalidOperand { opcode, operand } => write!(f, "invalid operand {} for {}", operand, opcode),
+            ParseError::UnknownLabel(label) => write!(f, "unknown label {}", label),
+            ParseError::DuplicateLabel(label) => write!(f, "label {} is defined more than once", label)
+        }
+    }
+}
+
+// registers are indexed by the program, so their number is capped before a register file is allocated for it
+const MAX_REGISTERS: usize = 256;
+
+#[derive(Default)]
+pub struct InstructionSet {
+    opcodes: Vec<OpcodeDefinition>,
+    opcode_ids: HashMap<String, Opcode>,
+    register_limit: usize
+}
+
+impl InstructionSet {
+    pub fn handheld() -> Self {
+        let mut instruction_set = Self { register_limit: ACCUMULATOR + 1, ..Self::default() };
+        instruction_set.register("nop", vec![OperandType::Value], ControlFlow::Next, |_registers, _operands| Some(1));
+        instruction_set.register("acc", vec![OperandType::Value], ControlFlow::Next, |registers, operands| {
+            registers[ACCUMULATOR] = registers[ACCUMULATOR].checked_add(operands[0].value(registers))?;
+            Some(1)
+        });
+        instruction_set.register("jmp", vec![OperandType::Value], ControlFlow::Jump { operand: 0 }, |registers, operands| Some(operands[0].value(registers)));
+        instruction_set
+    }
+
+    pub fn with_registers() -> Self {
+        let mut instruction_set = Self::handheld();
+        instruction_set.register_limit = MAX_REGISTERS;
+        instruction_set.register("cpy", vec![OperandType::Value, OperandType::Register], ControlFlow::Next, |registers, operands| {
+            if let Operand::Register(register) = operands[1] {
+                registers[register] = operands[0].value(registers);
+            }
+            Some(1)
+        });
+        instruction_set.register("add", vec![OperandType::Register, OperandType::Value], ControlFlow::Next, |registers, operands| {
+            if let Operand::Register(register) = operands[0] {
+                registers[register] = registers[register].checked_add(operands[1].value(registers))?;
+            }
+            Some(1)
+        });
+        instruction_set.register("jnz", vec![OperandType::Register, OperandType::Value], ControlFlow::Branch { operand: 1 }, |registers, operands| {
+            if operands[0].value(registers) != 0 {
+                Some(operands[1].value(registers))
+            } else {
+                Some(1)
+            }
+        });
+        instruction_set
+    }
+
+    pub fn register(&mut self, name: &str, operand_types: Vec<OperandType>, control_flow: ControlFlow, execute: Execute) -> Opcode {
+        let opcode = Opcode(self.opcodes.len());
+        self.opcodes.push(OpcodeDefinition { name: name.to_owned(), operand_types, control_flow, execute });
+        self.opcode_ids.insert(name.to_owned(), opcode);
+        opcode
+    }
+
+    pub fn opcode(&self, name: &str) -> Option<Opcode> {
+        self.opcode_ids.get(name).copied()
+    }
+
+    pub fn definition(&self, opcode: Opcode) -> &OpcodeDefinition {
+        &self.opcodes[opcode.0]
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Instruction, ParseError> {
+        let mut parts = input.split_whitespace();
+        let name = parts.next().unwrap_or_default();
+        let opcode = self.opcode(name).ok_or_else(|| ParseError::UnknownOpcode(name.to_owned()))?;
+        let definition = self.definition(opcode);
+        let operands: Vec<&str> = parts.collect();
+        if operands.len() != definition.operand_types.len() {
+            return Err(ParseError::WrongOperandCount { opcode: name.to_owned(), expected: definition.operand_types.len(), actual: operands.len() });
+        }
+        let operands = operands.iter().zip(&definition.operand_types)
+            .map(|(operand, &operand_type)| Operand::parse(operand, operand_type, self.register_limit)
+                .ok_or_else(|| ParseError::InvalidOperand { opcode: name.to_owned(), operand: operand.to_string() }))
+            .collect::<Result<Vec<Operand>, ParseError>>()?;
+        Ok(Instruction { opcode, operands })
+    }
+
+    // instruction indices that can follow the instruction at index, jumps by a register value are not followed
+    pub fn successors(&self, instruction: &Instruction, index: usize) -> Vec<isize> {
+        let next = index as isize + 1;
+        match self.definition(instruction.opcode).control_flow {
+            ControlFlow::Next => vec![next],
+            ControlFlow::Jump { .. } => self.jump_target(instruction, index).into_iter().collect(),
+            ControlFlow::Branch { .. } => std::iter::once(next).chain(self.jump_target(instruction, index)).collect()
+        }
+    }
+
+    // None for register jumps and for offsets that leave the range of isize, which are out of the program anyway
+    pub fn jump_target(&self, instruction: &Instruction, index: usize) -> Option<isize> {
+        let operand = self.definition(instruction.opcode).control_flow.jump_operand()?;
+        match instruction.operands[operand] {
+            Operand::Value(offset) => (index as isize).checked_add(offset),
+            Operand::Register(_) => None
+        }
+    }
+
+    pub fn format(&self, instruction: &Instruction) -> String {
+        let mut parts = vec![self.definition(instruction.opcode).name.clone()];
+        parts.extend(instruction.operands.iter().map(|operand| operand.to_string()));
+        parts.join(" ")
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct HaltConditions {
+    pub max_instruction_visits: Option<usize>,
+    pub max_steps: Option<usize>
+}
+
+impl Default for HaltConditions {
+    fn default() -> Self {
+        Self {
+            max_instruction_visits: Some(1),
+            max_steps: None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExecutionResult {
+    Terminated,
+    InfiniteLoop { ic: usize },
+    JumpOutOfBounds { ic: isize },
+    NegativeIc { ic: isize },
+    ArithmeticOverflow { ic: usize },
+    StepLimitReached { steps: usize }
+}
+
+impl fmt::Display for ExecutionResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionResult::Terminated => write!(f, "terminated"),
+            ExecutionResult::InfiniteLoop { ic } => write!(f, "infinite loop at instruction {}", ic),
+            ExecutionResult::JumpOutOfBounds { ic } => write!(f, "jump out of bounds to instruction {}", ic),
+            ExecutionResult::NegativeIc { ic } => write!(f, "negative instruction counter {}", ic),
+            ExecutionResult::ArithmeticOverflow { ic } => write!(f, "arithmetic overflow at instruction {}", ic),
+            ExecutionResult::StepLimitReached { steps } => write!(f, "step limit of {} reached", steps)
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TraceEntry {
+    pub ic: usize,
+    pub instruction: Instruction,
+    pub visit: usize,
+    pub accumulator_before: isize,
+    pub accumulator_after: isize
+}
+
+pub struct CPU<'a> {
+    instruction_set: &'a InstructionSet,
+    program: &'a [Instruction],
+    halt_conditions: HaltConditions,
+    pub ic: isize,
+    pub registers: Vec<isize>,
+    pub visited_instruction_count: Vec<usize>,
+    pub steps: usize,
+    pub trace: Option<Vec<TraceEntry>>,
+    overflow_ic: Option<usize>
+}
+
+impl<'a> CPU<'a> {
+    pub fn new(instruction_set: &'a InstructionSet, program: &'a [Instruction], register_count: usize, halt_conditions: HaltConditions) -> Self {
+        CPU {
+            instruction_set,
+            program,
+            halt_conditions,
+            ic: 0,
+            registers: vec![0; register_count.max(ACCUMULATOR + 1)],
+            visited_instruction_count: vec![0; program.len()],
+            steps: 0,
+            trace: None,
+            overflow_ic: None
+        }
+    }
+
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    pub fn instruction_set(&self) -> &'a InstructionSet {
+        self.instruction_set
+    }
+
+    pub fn program(&self) -> &'a [Instruction] {
+        self.program
+    }
+
+    pub fn format_trace_entry(&self, entry: &TraceEntry) -> String {
+        format!("{:>5}: {:<12} visit {:<3} acc {} -> {}", entry.ic, self.instruction_set.format(&entry.instruction),
+            entry.visit, entry.accumulator_before, entry.accumulator_after)
+    }
+
+    pub fn execute(instruction_set: &'a InstructionSet, program: &'a [Instruction]) -> (Self, ExecutionResult) {
+        let mut cpu = CPU::new(instruction_set, program, ACCUMULATOR + 1, HaltConditions::default());
+        let result = cpu.run();
+        (cpu, result)
+    }
+
+    pub fn accumulator(&self) -> isize {
+        self.registers[ACCUMULATOR]
+    }
+
+    fn halt_reason(&self) -> Option<ExecutionResult> {
+        if let Some(ic) = self.overflow_ic {
+            return Some(ExecutionResult::ArithmeticOverflow { ic });
+        }
+        if self.ic < 0 {
+            return Some(ExecutionResult::NegativeIc { ic: self.ic });
+        }
+        let instruction_index = self.ic as usize;
+        if instruction_index == self.program.len() {
+            Some(ExecutionResult::Terminated)
+        } else if instruction_index > self.program.len() {
+            Some(ExecutionResult::JumpOutOfBounds { ic: self.ic })
+        } else if self.halt_conditions.max_instruction_visits.is_some_and(|max_visits| self.visited_instruction_count[instruction_index] >= max_visits) {
+            Some(ExecutionResult::InfiniteLoop { ic: instruction_index })
+        } else if self.halt_conditions.max_steps.is_some_and(|max_steps| self.steps >= max_steps) {
+            Some(ExecutionResult::StepLimitReached { steps: self.steps })
+        } else {
+            None
+        }
+    }
+
+    pub fn step(&mut self) -> Option<ExecutionResult> {
+        if let Some(result) = self.halt_reason() {
+            return Some(result);
+        }
+        let instruction_index = self.ic as usize;
+        self.visited_instruction_count[instruction_index] += 1;
+        let instruction = &self.program[instruction_index];
+        let accumulator_before = self.accumulator();
+        let execute = self.instruction_set.definition(instruction.opcode).execute;
+        let offset = match execute(&mut self.registers, &instruction.operands) {
+            Some(offset) => offset,
+            None => {
+                self.overflow_ic = Some(instruction_index);
+                return self.halt_reason();
+            }
+        };
+        // a jump past the range of isize ends up out of bounds either way
+        self.ic = self.ic.saturating_add(offset);
+        self.steps += 1;
+        let accumulator_after = self.accumulator();
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceEntry {
+                ic: instruction_index,
+                instruction: instruction.clone(),
+                visit: self.visited_instruction_count[instruction_index],
+                accumulator_before,
+                accumulator_after
+            });
+        }
+        None
+    }
+
+    pub fn run(&mut self) -> ExecutionResult {
+        loop {
+            if let Some(result) = self.step() {
+                return result;
+            }
+        }
+    }
+}
@@ -1,60 +1,73 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::collections::HashMap;
+use std::ops::Range;
+use crate::sums;
 
-fn find_pair_with_sum(array: &[usize], sum: usize) -> Option<(usize, usize)> {
-    for (a_idx, a) in array.iter().enumerate() {
-        for (b_idx, b) in array[a_idx + 1..].iter().enumerate() {
-            if a + b == sum {
-                return Some((a_idx, b_idx))
-            }
-        }
-    }
-    None
-}
+mod validator;
 
-fn find_consecutive_slice_with_sum(array: &[usize], target_sum: usize) -> Option<(usize, usize)> {
-    let mut start_idx = 0;
-    let mut end_idx = 1;
-    let mut sum = array[start_idx..end_idx + 1].iter().sum::<usize>() as isize;
-    loop {
-        if sum == target_sum as isize {
-            return Some((start_idx, end_idx));
-        } else if sum > target_sum as isize {
-            sum -= array[start_idx] as isize;
-            start_idx += 1;
-        } else {
-            end_idx += 1;
-            sum += array[end_idx] as isize;
-        }
-        if start_idx == array.len() || end_idx + 1 > array.len() {
-            return None;
+pub use validator::{InvalidNumber, XmasValidator};
+
+const MIN_RANGE_LENGTH: usize = 2;
+
+// every half-open range whose values add up to the target, found via prefix sums so negative values are fine
+pub fn find_ranges_with_sum(numbers: &[isize], target_sum: isize, min_length: usize) -> Vec<Range<usize>> {
+    let min_length = min_length.max(1);
+    let mut prefix_sums = Vec::with_capacity(numbers.len() + 1);
+    prefix_sums.push(0);
+    for number in numbers {
+        prefix_sums.push(prefix_sums.last().unwrap() + number);
+    }
+    let mut starts_by_prefix_sum: HashMap<isize, Vec<usize>> = HashMap::new();
+    let mut ranges = Vec::new();
+    for end in min_length..prefix_sums.len() {
+        starts_by_prefix_sum.entry(prefix_sums[end - min_length]).or_default().push(end - min_length);
+        if let Some(starts) = starts_by_prefix_sum.get(&(prefix_sums[end] - target_sum)) {
+            ranges.extend(starts.iter().map(|&start| start..end));
         }
     }
+    ranges
 }
 
-fn find_number_without_preamble_sum(numbers: &[usize], preamble_size: usize) -> Option<usize> {
-    let preamble_iterator = numbers.windows(preamble_size);
-    for (i, preamble) in preamble_iterator.enumerate() {
-        let target = numbers[i + preamble_size];
-        if find_pair_with_sum(preamble, target).is_none() {
-            return Some(target)
-        }
-    }
-    None
+fn weakness_ranges_in(numbers: &[usize], preamble_size: usize, min_length: usize) -> Option<(usize, Vec<Range<usize>>)> {
+    let target = XmasValidator::new(preamble_size).validate(numbers.iter().copied()).first()?.value;
+    let signed_numbers: Vec<isize> = numbers.iter().map(|&number| number as isize).collect();
+    Some((target, find_ranges_with_sum(&signed_numbers, target as isize, min_length)))
 }
 
 pub fn solve(input: &File, preamble_size: usize) -> (Option<usize>, Option<usize>) {
     let numbers = parse_input(input);
-    let target = find_number_without_preamble_sum(&numbers, preamble_size);
-    let mut target_sum_addends_sum = None;
-    if let Some(target) = target {
-        target_sum_addends_sum = find_consecutive_slice_with_sum(&numbers, target).and_then(|(start_idx, end_idx)| {
-            let slice = &numbers[start_idx..end_idx + 1];
-            Some(slice.iter().min().unwrap() + slice.iter().max().unwrap())
-        });
+    match weakness_ranges_in(&numbers, preamble_size, MIN_RANGE_LENGTH) {
+        Some((target, ranges)) => {
+            let weakness = ranges.first().map(|range| {
+                let slice = &numbers[range.clone()];
+                slice.iter().min().unwrap() + slice.iter().max().unwrap()
+            });
+            (Some(target), weakness)
+        },
+        None => (None, None)
     }
-    (target, target_sum_addends_sum)
+}
+
+pub fn weakness_ranges(input: &File, preamble_size: usize, min_length: usize) -> Vec<Range<usize>> {
+    weakness_ranges_in(&parse_input(input), preamble_size, min_length).map(|(_, ranges)| ranges).unwrap_or_default()
+}
+
+// the first pair of different preamble numbers adding up to each valid number, with indices into the whole input
+pub fn preamble_pairs(input: &File, preamble_size: usize) -> Vec<(usize, [sums::Addend; 2])> {
+    let numbers: Vec<i64> = parse_input(input).into_iter().map(|number| number as i64).collect();
+    (preamble_size..numbers.len()).filter_map(|position| {
+        let preamble = &numbers[position - preamble_size..position];
+        let [a, b] = sums::find_pair_with_sum(preamble, numbers[position], true)?;
+        let offset = position - preamble_size;
+        Some((position, [sums::Addend { index: a.index + offset, ..a }, sums::Addend { index: b.index + offset, ..b }]))
+    }).collect()
+}
+
+pub fn invalid_numbers(input: &File, preamble_size: usize) -> Vec<InvalidNumber> {
+    let numbers = BufReader::new(input).lines().map(|line| line.unwrap().parse().unwrap());
+    XmasValidator::new(preamble_size).validate(numbers)
 }
 
 fn parse_input(input: &File) -> Vec<usize> {
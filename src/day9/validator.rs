@@ -0,0 +1,73 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+pub struct InvalidNumber {
+    pub position: usize,
+    pub value: usize
+}
+
+impl fmt::Display for InvalidNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.value, self.position)
+    }
+}
+
+// keeps the sums of all pairs of distinct numbers in the window, so each number is checked in O(preamble size)
+pub struct XmasValidator {
+    preamble_size: usize,
+    window: VecDeque<usize>,
+    pair_sums: HashMap<usize, usize>,
+    position: usize
+}
+
+impl XmasValidator {
+    pub fn new(preamble_size: usize) -> Self {
+        XmasValidator {
+            preamble_size,
+            window: VecDeque::with_capacity(preamble_size + 1),
+            pair_sums: HashMap::new(),
+            position: 0
+        }
+    }
+
+    // None while the preamble is still being read
+    pub fn push(&mut self, number: usize) -> Option<bool> {
+        let valid = if self.window.len() < self.preamble_size {
+            None
+        } else {
+            Some(self.pair_sums.get(&number).is_some_and(|&count| count > 0))
+        };
+        for &other in &self.window {
+            if other != number {
+                *self.pair_sums.entry(other + number).or_insert(0) += 1;
+            }
+        }
+        self.window.push_back(number);
+        if self.window.len() > self.preamble_size {
+            let oldest = self.window.pop_front().unwrap();
+            for &other in &self.window {
+                if other != oldest {
+                    let sum = oldest + other;
+                    let count = self.pair_sums.get_mut(&sum).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        self.pair_sums.remove(&sum);
+                    }
+                }
+            }
+        }
+        self.position += 1;
+        valid
+    }
+
+    pub fn validate(&mut self, numbers: impl IntoIterator<Item = usize>) -> Vec<InvalidNumber> {
+        let mut invalid_numbers = Vec::new();
+        for value in numbers {
+            let position = self.position;
+            if self.push(value) == Some(false) {
+                invalid_numbers.push(InvalidNumber { position, value });
+            }
+        }
+        invalid_numbers
+    }
+}
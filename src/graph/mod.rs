@@ -0,0 +1,55 @@
+// Tarjan's strongly connected components over the nodes 0..node_count, started from the nodes in the given order,
+// keeping only components that form a cycle; each component comes back with its nodes sorted
+pub fn find_cycles<'a>(node_count: usize, starts: impl IntoIterator<Item = usize>, successors: impl Fn(usize) -> &'a [usize]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+    let mut indices = vec![UNVISITED; node_count];
+    let mut low_links = vec![0; node_count];
+    let mut on_stack = vec![false; node_count];
+    let mut stack = Vec::new();
+    let mut cycles = Vec::new();
+    let mut next_index = 0;
+    for start in starts {
+        if indices[start] != UNVISITED {
+            continue;
+        }
+        let mut call_stack = vec![(start, 0)];
+        while let Some(&(node, edge_idx)) = call_stack.last() {
+            if edge_idx == 0 && indices[node] == UNVISITED {
+                indices[node] = next_index;
+                low_links[node] = next_index;
+                next_index += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            }
+            if let Some(&successor) = successors(node).get(edge_idx) {
+                call_stack.last_mut().unwrap().1 += 1;
+                if indices[successor] == UNVISITED {
+                    call_stack.push((successor, 0));
+                } else if on_stack[successor] {
+                    low_links[node] = low_links[node].min(indices[successor]);
+                }
+                continue;
+            }
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                low_links[parent] = low_links[parent].min(low_links[node]);
+            }
+            if low_links[node] == indices[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                if component.len() > 1 || successors(node).contains(&node) {
+                    component.sort_unstable();
+                    cycles.push(component);
+                }
+            }
+        }
+    }
+    cycles
+}
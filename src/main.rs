@@ -2,7 +2,12 @@
 extern crate lazy_static;
 
 use std::fs::File;
+use std::env;
+use std::io;
 use std::fmt;
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+use day7::Counter;
 
 mod day1;
 mod day2;
@@ -22,6 +27,8 @@ mod day15;
 mod day16;
 mod day17;
 mod day18;
+mod sums;
+mod graph;
 
 fn open_file(filename: &str) -> File {
     File::open(&filename).expect(&format!("Could not open file {}", filename))
@@ -38,6 +45,19 @@ fn print_result(tag: &str, result: &Option<impl fmt::Display>) {
     }
 }
 
+fn print_errors(tag: &str, errors: &[impl fmt::Display]) {
+    for error in errors {
+        println!("{} input is invalid: {}", tag, error)
+    }
+}
+
+fn print_validated_result(tag: &str, result: &Result<Option<impl fmt::Display>, Vec<impl fmt::Display>>) {
+    match result {
+        Ok(result) => print_result(tag, result),
+        Err(errors) => print_errors(tag, errors)
+    }
+}
+
 pub fn solve_day1() {
     let input = open_file(&get_filename("day1"));
     print_result("Day 1", &day1::solve(&input, 2))
@@ -80,64 +100,266 @@ pub fn solve_day4_part_2() {
 
 pub fn solve_day5() {
     let input = open_file(&get_filename("day5"));
-    print_result("Day 5", &day5::solve(&input, day5::SearchCriteria::HIGHEST))
+    print_validated_result("Day 5", &day5::solve(&input, &day5::PlaneLayout::default(), day5::SearchCriteria::HIGHEST))
 }
 
 pub fn solve_day5_part_2() {
     let input = open_file(&get_filename("day5"));
-    print_result("Day 5 part 2", &day5::solve(&input, day5::SearchCriteria::EMPTY))
+    print_validated_result("Day 5 part 2", &day5::solve(&input, &day5::PlaneLayout::default(), day5::SearchCriteria::EMPTY))
+}
+
+pub fn solve_day5_boarding_pass() {
+    let input = open_file(&get_filename("day5"));
+    let layout = day5::PlaneLayout::default();
+    let free_seat_id = day5::solve(&input, &layout, day5::SearchCriteria::EMPTY);
+    print_validated_result("Day 5 boarding pass", &free_seat_id.map(|seat_id| seat_id.map(|seat_id| layout.pass_for_seat_id(seat_id))))
+}
+
+pub fn solve_day5_occupancy() {
+    let input = open_file(&get_filename("day5"));
+    print_result("Day 5 occupancy", &Some(day5::analyse_occupancy(&input, &day5::PlaneLayout::default())))
+}
+
+pub fn solve_day5_seat_map() {
+    let input = open_file(&get_filename("day5"));
+    print_validated_result("Day 5 seat map", &day5::render_seat_map(&input, &day5::PlaneLayout::default()))
 }
 
 pub fn solve_day6() {
     let input = open_file(&get_filename("day6"));
-    print_result("Day 6", &day6::solve(&input, &day6::AnyonesAnswer))
+    print_validated_result("Day 6", &day6::solve(&input, &day6::AnyonesAnswer))
 }
 
 pub fn solve_day6_part_2() {
     let input = open_file(&get_filename("day6"));
-    print_result("Day 6 part 2", &day6::solve(&input, &day6::EveryonesAnswer))
+    print_validated_result("Day 6 part 2", &day6::solve(&input, &day6::EveryonesAnswer))
+}
+
+pub fn solve_day6_at_least_two() {
+    let input = open_file(&get_filename("day6"));
+    print_validated_result("Day 6 at least two", &day6::solve(&input, &day6::AtLeastAnswer(2)))
+}
+
+pub fn solve_day6_exactly_one() {
+    let input = open_file(&get_filename("day6"));
+    print_validated_result("Day 6 exactly one", &day6::solve(&input, &day6::ExactlyOneAnswer))
+}
+
+pub fn solve_day6_symmetric_difference() {
+    let input = open_file(&get_filename("day6"));
+    print_validated_result("Day 6 symmetric difference", &day6::solve(&input, &day6::SymmetricDifferenceAnswer))
+}
+
+pub fn solve_day6_breakdown() {
+    let input = open_file(&get_filename("day6"));
+    for (i, group) in day6::group_breakdown(&input).enumerate() {
+        print_validated_result(&format!("Day 6 group {}", i + 1), &group.map(Some))
+    }
+}
+
+pub fn solve_day6_histogram() {
+    let input = open_file(&get_filename("day6"));
+    print_validated_result("Day 6 histogram", &day6::question_histogram(&input, &day6::EveryonesAnswer))
 }
 
 pub fn solve_day7() {
+    const TARGET_NAME: &str = "shiny gold";
     let input = open_file(&get_filename("day7"));
-    print_result("Day 7", &day7::solve(&input, &day7::UniqueRootsCounter))
+    print_validated_result("Day 7", &day7::solve(&input, &day7::UniqueRootsCounter, TARGET_NAME))
 }
 
 pub fn solve_day7_part_2() {
+    const TARGET_NAME: &str = "shiny gold";
     let input = open_file(&get_filename("day7"));
-    print_result("Day 7 part 2", &day7::solve(&input, &day7::BagCapacityCounter))
+    print_validated_result("Day 7 part 2", &day7::solve(&input, &day7::BagCapacityCounter, TARGET_NAME))
+}
+
+pub fn solve_day7_dot() {
+    const TARGET_NAME: &str = "shiny gold";
+    let input = open_file(&get_filename("day7"));
+    match day7::BagGraph::parse(&input) {
+        Ok(graph) => {
+            let export = day7::GraphExport {
+                highlighted: day7::UniqueRootsCounter.counted_bags(&graph, TARGET_NAME).unwrap_or_default(),
+                ..Default::default()
+            };
+            print_result("Day 7 dot", &Some(graph.to_dot(&export)))
+        },
+        Err(errors) => print_errors("Day 7 dot", &errors)
+    }
+}
+
+pub fn solve_day7_mermaid() {
+    const TARGET_NAME: &str = "shiny gold";
+    let input = open_file(&get_filename("day7"));
+    match day7::BagGraph::parse(&input) {
+        Ok(graph) => {
+            let export = day7::GraphExport {
+                subgraph_root: Some(TARGET_NAME),
+                highlighted: day7::BagCapacityCounter.counted_bags(&graph, TARGET_NAME).unwrap_or_default()
+            };
+            print_result("Day 7 mermaid", &Some(graph.to_mermaid(&export)))
+        },
+        Err(errors) => print_errors("Day 7 mermaid", &errors)
+    }
+}
+
+pub fn solve_day7_bag_report() {
+    const TARGET_NAME: &str = "shiny gold";
+    let input = open_file(&get_filename("day7"));
+    let graph = match day7::BagGraph::parse(&input) {
+        Ok(graph) => graph,
+        Err(errors) => return print_errors("Day 7 bag report", &errors)
+    };
+    let join = |bags: BTreeSet<&str>| bags.into_iter().collect::<Vec<&str>>().join(", ");
+    print_result("Day 7 roots", &Some(join(graph.roots())));
+    print_result("Day 7 leaves", &Some(join(graph.leaves())));
+    print_result("Day 7 ancestors", &graph.ancestors(TARGET_NAME).map(join));
+    print_result("Day 7 descendants", &graph.descendants(TARGET_NAME).map(join));
+    for root in graph.roots() {
+        for path in graph.paths(root, TARGET_NAME) {
+            print_result("Day 7 path", &Some(path))
+        }
+    }
+    for (bag, count) in graph.contained_breakdown(TARGET_NAME).unwrap_or_default() {
+        print_result(&format!("Day 7 {} bags inside", bag), &Some(count))
+    }
 }
 
 pub fn solve_day8() {
     let input = open_file(&get_filename("day8"));
-    print_result("Day 8", &day8::solve(&input, false))
+    print_validated_result("Day 8", &day8::solve(&input, false))
 }
 
 pub fn solve_day8_part_2() {
     let input = open_file(&get_filename("day8"));
-    print_result("Day 8 part 2", &day8::solve(&input, true))
+    print_validated_result("Day 8 part 2", &day8::solve(&input, true))
+}
+
+pub fn solve_day8_repair() {
+    let input = open_file(&get_filename("day8"));
+    print_validated_result("Day 8 repair", &day8::find_repair(&input))
+}
+
+pub fn solve_day8_repair_search() {
+    let input = open_file(&get_filename("day8"));
+    let search = day8::RepairSearch {
+        max_edits: 2,
+        edit_kinds: vec![day8::EditKind::FlipJump, day8::EditKind::NegateAccumulation, day8::EditKind::Delete],
+        execution_budget: 100_000
+    };
+    print_validated_result("Day 8 repair search", &day8::search_repairs(&input, &search))
+}
+
+pub fn solve_day8_disassembly() {
+    let input = open_file(&get_filename("day8"));
+    print_validated_result("Day 8 disassembly", &day8::disassemble(&input))
+}
+
+pub fn solve_day8_analysis() {
+    let input = open_file(&get_filename("day8"));
+    print_validated_result("Day 8 analysis", &day8::analyse(&input))
+}
+
+pub fn solve_day8_trace() {
+    let input = open_file(&get_filename("day8"));
+    print_validated_result("Day 8 trace", &day8::execution_trace(&input))
+}
+
+pub fn debug_day8() {
+    let input = open_file(&get_filename("day8"));
+    let stdin = io::stdin();
+    if let Err(errors) = day8::debug(&input, stdin.lock(), &mut io::stdout()) {
+        print_errors("Day 8 debugger", &errors)
+    }
+}
+
+pub fn solve_day8_execution_summary() {
+    let input = open_file(&get_filename("day8"));
+    print_validated_result("Day 8 execution summary", &day8::execution_summary(&input))
+}
+
+fn day9_preamble_size() -> usize {
+    const DEFAULT_PREAMBLE_SIZE: usize = 25;
+    env::args()
+        .find_map(|arg| arg.strip_prefix("--day9-preamble=").map(|size| size.parse().expect("Expected preamble size")))
+        .unwrap_or(DEFAULT_PREAMBLE_SIZE)
 }
 
 pub fn solve_day9() {
-    const PREAMBLE_SIZE: usize = 25;
     let input = open_file(&get_filename("day9"));
-    print_result("Day 9", &day9::solve(&input, PREAMBLE_SIZE).0)
+    print_result("Day 9", &day9::solve(&input, day9_preamble_size()).0)
 }
 
 pub fn solve_day9_part_2() {
-    const PREAMBLE_SIZE: usize = 25;
     let input = open_file(&get_filename("day9"));
-    print_result("Day 9 part 2", &day9::solve(&input, PREAMBLE_SIZE).1)
+    print_result("Day 9 part 2", &day9::solve(&input, day9_preamble_size()).1)
+}
+
+pub fn solve_day9_invalid_numbers() {
+    let input = open_file(&get_filename("day9"));
+    for invalid in day9::invalid_numbers(&input, day9_preamble_size()) {
+        println!("Day 9 invalid number: {}", invalid)
+    }
+}
+
+pub fn solve_day9_pairs() {
+    let input = open_file(&get_filename("day9"));
+    for (position, [a, b]) in day9::preamble_pairs(&input, day9_preamble_size()) {
+        println!("Day 9 number at position {} is the sum of {} and {}", position, a, b)
+    }
+}
+
+pub fn solve_day9_weakness_ranges() {
+    const MIN_RANGE_LENGTH: usize = 2;
+    let input = open_file(&get_filename("day9"));
+    for range in day9::weakness_ranges(&input, day9_preamble_size(), MIN_RANGE_LENGTH) {
+        println!("Day 9 weakness range: {}..={}", range.start, range.end - 1)
+    }
 }
 
 pub fn solve_day10() {
     let input = open_file(&get_filename("day10"));
-    print_result("Day 10", &day10::solve(&input, &day10::GreedyConnectionDiffMultiplication))
+    print_result("Day 10", &day10::solve(&input, &day10::GreedyConnectionDiffMultiplication, &day10::JoltageRules::default()))
 }
 
 pub fn solve_day10_part_2() {
     let input = open_file(&get_filename("day10"));
-    print_result("Day 10 part 2", &day10::solve(&input, &day10::ConnectionCombinationCount))
+    print_result("Day 10 part 2", &day10::solve(&input, &day10::ConnectionCombinationCount::default(), &day10::JoltageRules::default()))
+}
+
+pub fn solve_day10_arrangements_modulo() {
+    const MODULO: u32 = 1_000_000_007;
+    let input = open_file(&get_filename("day10"));
+    let counter = day10::ConnectionCombinationCount { modulo: Some(MODULO.into()) };
+    print_result("Day 10 arrangements modulo 1000000007", &day10::solve(&input, &counter, &day10::JoltageRules::default()))
+}
+
+pub fn solve_day10_arrangements() {
+    const MAX_LISTED_ARRANGEMENTS: usize = 20;
+    let input = open_file(&get_filename("day10"));
+    let rules = day10::JoltageRules::default();
+    for arrangement in day10::arrangements(&input, &rules).take(MAX_LISTED_ARRANGEMENTS) {
+        println!("Day 10 arrangement: {}", arrangement)
+    }
+}
+
+pub fn solve_day10_sample_arrangement() {
+    let input = open_file(&get_filename("day10"));
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_nanos() as u64);
+    let arrangement = day10::sample_arrangement(&input, &day10::JoltageRules::default(), &mut day10::Random::new(seed));
+    print_result("Day 10 random arrangement", &arrangement)
+}
+
+pub fn solve_day10_mandatory_adapters() {
+    let input = open_file(&get_filename("day10"));
+    let adapters: Vec<String> = day10::mandatory_adapters(&input, &day10::JoltageRules::default()).iter().map(|jolts| jolts.to_string()).collect();
+    println!("Day 10 mandatory adapters: {}", adapters.join(", "))
+}
+
+pub fn solve_day10_chain() {
+    let input = open_file(&get_filename("day10"));
+    print_result("Day 10 adapter chain", &day10::adapter_chain(&input, &day10::JoltageRules::default()))
 }
 
 pub fn solve_day11() {
@@ -150,6 +372,14 @@ pub fn solve_day11_part_2() {
     print_result("Day 11 part 2", &day11::solve(&input, &day11::VisibleNeighbourConfiguration))
 }
 
+pub fn solve_day11_rules() {
+    const DEFAULT_RULES: &str = "B0/S0-3/moore";
+    let rules = env::args().find_map(|arg| arg.strip_prefix("--day11-rules=").map(str::to_owned)).unwrap_or_else(|| DEFAULT_RULES.to_owned());
+    let config = day11::RuleConfiguration::parse(&rules).unwrap_or_else(|| panic!("Invalid seating rules {}", rules));
+    let input = open_file(&get_filename("day11"));
+    print_result(&format!("Day 11 with rules {}", rules), &day11::solve(&input, &config))
+}
+
 pub fn solve_day12() {
     let input = open_file(&get_filename("day12"));
     let mut boat = day12::BoatByItself::default();
@@ -225,6 +455,9 @@ pub fn solve_day18_part_2() {
 }
 
 fn main() {
+    if env::args().any(|arg| arg == "--debug-day8") {
+        return debug_day8();
+    }
     solve_day18();
     solve_day18_part_2();
 }
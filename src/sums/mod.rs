@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Addend {
+    pub index: usize,
+    pub value: i64
+}
+
+impl fmt::Display for Addend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at index {}", self.value, self.index)
+    }
+}
+
+// two entries at different indices, looked up in a hash map of the values seen so far
+pub fn find_pair_with_sum(values: &[i64], target_sum: i64, distinct_values: bool) -> Option<[Addend; 2]> {
+    find_pair_from(values, 0, target_sum, distinct_values)
+}
+
+// k entries at increasing indices, O(n^(k-1)) as the last two are found with a hash lookup
+pub fn find_addends_with_sum(values: &[i64], target_sum: i64, count: usize) -> Option<Vec<Addend>> {
+    find_addends_from(values, 0, target_sum, count)
+}
+
+fn find_pair_from(values: &[i64], start: usize, target_sum: i64, distinct_values: bool) -> Option<[Addend; 2]> {
+    let mut seen = HashMap::new();
+    for (index, &value) in values.iter().enumerate().skip(start) {
+        if distinct_values && target_sum - value == value {
+            continue;
+        }
+        if let Some(&other_index) = seen.get(&(target_sum - value)) {
+            return Some([Addend { index: other_index, value: target_sum - value }, Addend { index, value }]);
+        }
+        seen.entry(value).or_insert(index);
+    }
+    None
+}
+
+fn find_addends_from(values: &[i64], start: usize, target_sum: i64, count: usize) -> Option<Vec<Addend>> {
+    match count {
+        0 => if target_sum == 0 { Some(Vec::new()) } else { None },
+        1 => values[start..].iter().position(|&value| value == target_sum)
+            .map(|offset| vec![Addend { index: start + offset, value: target_sum }]),
+        2 => find_pair_from(values, start, target_sum, false).map(|pair| pair.to_vec()),
+        _ => (start..values.len()).find_map(|index| {
+            let addend = Addend { index, value: values[index] };
+            find_addends_from(values, index + 1, target_sum - addend.value, count - 1).map(|mut addends| {
+                addends.insert(0, addend);
+                addends
+            })
+        })
+    }
+}